@@ -17,6 +17,16 @@ pub mod drawing {
         mouse_x: f32,
         mouse_y: f32,
         pub last_click: time::Instant,
+        // keyboard
+        typed: String,
+        backspace: bool,
+        enter: bool,
+    }
+
+    pub enum InputEvent {
+        Idle,
+        Clicked,
+        Submitted,
     }
 
     #[derive(Clone, Copy)]
@@ -81,6 +91,9 @@ pub mod drawing {
                 mouse_x: 0.0,
                 mouse_y: 0.0,
                 last_click: time::Instant::now(),
+                typed: String::new(),
+                backspace: false,
+                enter: false,
             }
         }
 
@@ -90,6 +103,20 @@ pub mod drawing {
             //println!("m {}x{}", x, y);
         }
 
+        pub fn process_keyboard(&mut self, typed: String, backspace: bool, enter: bool) {
+            self.typed = typed;
+            self.backspace = backspace;
+            self.enter = enter;
+        }
+
+        // hides the click from widgets drawn next (e.g. under a dialog), returns it to give it back later
+        pub fn take_mouse(&mut self) -> (f32, f32) {
+            let mouse = (self.mouse_x, self.mouse_y);
+            self.mouse_x = -1.0;
+            self.mouse_y = -1.0;
+            mouse
+        }
+
         pub fn check_click(
             &self,
             x1: f32,
@@ -148,6 +175,81 @@ pub mod drawing {
             );
         }
 
+        // text field, the focused one receives the typed characters
+        pub fn draw_input(
+            &mut self,
+            text: &mut String,
+            location: Location4,
+            focused: bool,
+        ) -> InputEvent {
+            if focused {
+                // backspace first, a key typed in the same frame must not be the one removed
+                if self.backspace {
+                    text.pop();
+                }
+                text.push_str(&self.typed);
+            }
+
+            let background = if focused {
+                Color::new(255, 200, 200, 200)
+            } else {
+                Color::new(255, 140, 140, 140)
+            };
+            self.draw_square(location, background);
+            // show the end of long texts
+            let max_chars = (location.w / 7.5) as usize;
+            let chars: Vec<char> = text.chars().collect();
+            let mut visible: String = chars[chars.len().saturating_sub(max_chars)..]
+                .iter()
+                .collect();
+            if focused {
+                visible.push('_');
+            }
+            self.draw_text(
+                &visible,
+                Location2::new(location.x + 3.0, location.y + location.h - 5.0),
+                Color::new(255, 0, 0, 0),
+                12.5,
+            );
+
+            if focused && self.enter {
+                InputEvent::Submitted
+            } else if self.check_click(
+                location.x,
+                location.y,
+                location.w,
+                location.h,
+                self.mouse_x,
+                self.mouse_y,
+                1.,
+                1.,
+            ) {
+                InputEvent::Clicked
+            } else {
+                InputEvent::Idle
+            }
+        }
+
+        // underlined text, true when clicked. The font is monospace so the width is known from the length.
+        pub fn draw_link(&mut self, text: &str, location: Location2, size: f32) -> bool {
+            let width = text.chars().count() as f32 * size * 0.6;
+            self.draw_text(text, location, Color::new(255, 120, 160, 255), size);
+            self.draw_square(
+                Location4::new(location.x, location.y + 2.0, width, 1.0),
+                Color::new(255, 120, 160, 255),
+            );
+            self.check_click(
+                location.x,
+                location.y - size,
+                width,
+                size + 3.0,
+                self.mouse_x,
+                self.mouse_y,
+                1.,
+                1.,
+            )
+        }
+
         pub fn draw_text(&mut self, text: &str, location: Location2, color: Color, size: f32) {
             self.dt.draw_text(
                 &self.font,
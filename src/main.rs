@@ -2,55 +2,455 @@ mod drawing;
 mod ml_core;
 mod models;
 
-use drawing::drawing::{Color, Drawing, Location2, Location4};
+use drawing::drawing::{Color, Drawing, InputEvent, Location2, Location4};
 use font_kit::family_name::FamilyName;
 use font_kit::properties::Properties;
-use font_kit::{font::Font, source::SystemSource};
-use minifb::{MouseButton, MouseMode, Scale, ScaleMode, Window, WindowOptions};
-use ml_core::http_tools;
+use font_kit::source::SystemSource;
+use minifb::{
+    InputCallback, Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions,
+};
+use ml_core::crash::{self, CrashReport};
+use ml_core::download_queue::{DownloadQueue, ItemStatus};
+use ml_core::filter::{ReleaseFilter, SortedView};
+use ml_core::integrity::Checksum;
+use ml_core::java::{self, JavaRuntime, RuntimeSelection};
+use ml_core::markdown::{self, Style};
+use ml_core::process::{GameProcess, Session};
+use ml_core::{
+    download, http_tools, installed, launcher, profiles, providers, releases, runtimes, server,
+    sources,
+};
+use models::instance::{Instance, InstancesFile};
+use models::profile::Channel;
+use models::release_cache::ReleaseCache;
+use models::source::ReleaseSource;
+use models::version::{Release, ReleaseAsset};
 use nfd::Response;
 use parking_lot::Mutex;
-use raqote::{
-    DrawOptions, DrawTarget, PathBuilder, Point, SolidSource, Source, StrokeStyle, Transform,
-};
+use raqote::DrawTarget;
 use std::collections::HashMap;
-use std::io::Read;
 use std::sync::Arc;
-use std::{
-    fs::{self, File},
-    io::{self, Error},
-    thread, time,
-};
-use webbrowser;
+use std::{fs, io, thread, time};
 
 const WIDTH: usize = 854;
 const HEIGHT: usize = 480;
+/// Where the versions list starts, the search bar is above it
+const LIST_TOP: f32 = 50.0;
+/// How often the release sources are retried while offline
+const OFFLINE_RETRY: time::Duration = time::Duration::from_secs(30);
+/// Characters per line of the release notes pane
+const NOTES_WIDTH: usize = 70;
+
+struct State {
+    version_loading_error: bool,
+    /// Set when GitHub refused the release lists because of its rate limit
+    rate_limited_until: Option<chrono::DateTime<chrono::Local>>,
+    /// More release pages are on the way
+    versions_loading: bool,
+    /// Bumped by every reload so an older loader stops adding its pages
+    versions_generation: u32,
+    /// Bumped by every change of the list, the sorted view is rebuilt then
+    versions_revision: u64,
+    /// No source could be reached, the list shows the installed jars
+    offline: bool,
+    status_message: String,
+    java_runtimes: Vec<JavaRuntime>,
+    java_scanning: bool,
+    jre_installing: bool,
+    server_downloading: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Java,
+    Console,
+    Instances,
+    Server,
+    Notes,
+    Queue,
+}
+
+/// Text field that receives the keyboard
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    InstanceName,
+    ServerCommand,
+    Search,
+}
+
+/// Collects characters typed into the window, minifb calls it from `update`
+struct TypedChars(Arc<Mutex<String>>);
+
+impl InputCallback for TypedChars {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(c) = std::char::from_u32(uni_char).filter(|c| !c.is_control()) {
+            self.0.lock().push(c);
+        }
+    }
+}
+
+fn play_version(
+    instance: &Instance,
+    release: &Release,
+    state: &Arc<Mutex<State>>,
+    selection: &RuntimeSelection,
+) -> Option<GameProcess> {
+    let tag_name = release.tag_name.as_str();
+    // re-read on every launch so edits of Profiles.toml apply without a restart
+    let profile = match profiles::load() {
+        Ok(loaded) => profiles::resolve(&loaded, tag_name, release.channel),
+        Err(e) => {
+            state.lock().status_message = format!("Cannot read {}: {}", profiles::PROFILES_FILE, e);
+            return None;
+        }
+    };
+    let runtime = selection
+        .resolve(tag_name, &state.lock().java_runtimes)
+        .cloned();
+    // a jar found under another name is launched from where it is
+    let jar = release
+        .jar
+        .clone()
+        .unwrap_or_else(|| launcher::jar_path(&instance.path, &release.source, tag_name));
+    // checked here, a `NotFound` from the launch is a missing java or working directory
+    if !jar.is_file() {
+        state.lock().status_message =
+            format!("{} is not installed yet, press INSTALL first", tag_name);
+        return None;
+    }
+    let launched = launcher::launch(runtime.as_ref(), instance, tag_name, &jar, &profile);
+    let mut message = match &launched {
+        Ok(game) => format!(
+            "Started Mindustry {} with profile {} (pid {})",
+            tag_name,
+            profile.name,
+            game.session.lock().pid
+        ),
+        Err(e) => format!("Failed to start {}: {}", tag_name, e),
+    };
+    if let Some(runtime) = runtime {
+        let required = java::required_major(tag_name);
+        if runtime.major < required {
+            message = format!(
+                "{} (warning: needs Java {}+, got Java {})",
+                message, required, runtime.major
+            );
+        }
+    }
+    println!("{}", message);
+    state.lock().status_message = message;
+    if launched.is_ok() {
+        // an unknown jar is hashed first, neither the UI nor the list lock wait for that
+        let release = release.clone();
+        thread::spawn(move || {
+            if let Err(e) = installed::record_launch(&release, &jar) {
+                println!("cannot record launch of {}: {}", jar.display(), e);
+            }
+        });
+    }
+    launched.ok()
+}
+
+/// Changes the list only while `generation` is the current load. The check and the change hold
+/// the list lock, like `load_versions` does when it bumps the generation. False for an old load.
+fn update_versions(
+    versions: &Mutex<Vec<Release>>,
+    state: &Mutex<State>,
+    generation: u32,
+    change: impl FnOnce(&mut Vec<Release>),
+) -> bool {
+    let mut versions = versions.lock();
+    if state.lock().versions_generation != generation {
+        return false;
+    }
+    change(&mut versions);
+    state.lock().versions_revision += 1;
+    true
+}
+
+/// Matches the list against the installation directory again, after an install, a delete
+/// or a switch to another instance
+fn refresh_installed(
+    versions: Arc<Mutex<Vec<Release>>>,
+    state: Arc<Mutex<State>>,
+    install_dir: String,
+) {
+    thread::spawn(move || {
+        // hashing can take a while, the list stays usable meanwhile
+        let mut marked = versions.lock().clone();
+        installed::mark(
+            &mut marked,
+            &install_dir,
+            &mut installed::scan(&install_dir),
+            &installed::database(),
+        );
+        let mut versions = versions.lock();
+        for version in versions.iter_mut() {
+            if let Some(found) = marked
+                .iter()
+                .find(|found| found.source == version.source && found.tag_name == version.tag_name)
+            {
+                version.installed = found.installed;
+                version.jar = found.jar.clone();
+            }
+        }
+        state.lock().versions_revision += 1;
+    });
+}
+
+fn format_duration(duration: time::Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Header, output and log path of a process. `top` is where the header row starts.
+fn draw_session(drawing: &mut Drawing, session: &Session, scroll: usize, top: f32, visible: usize) {
+    let process_state = match (session.running(), session.exit_code) {
+        (true, _) => "running".to_string(),
+        (false, Some(code)) => format!("exited with code {}", code),
+        (false, None) => "killed".to_string(),
+    };
+    drawing.draw_text(
+        &format!(
+            "{} | pid {} | uptime {} | {}",
+            session.tag_name,
+            session.pid,
+            format_duration(session.uptime()),
+            process_state
+        ),
+        Location2::new(320.0, top + 14.0),
+        Color::new(255, 255, 255, 255),
+        13.0,
+    );
+
+    let box_height = visible as f32 * 13.0 + 3.0;
+    drawing.draw_square(
+        Location4::new(320.0, top + 20.0, 520.0, box_height),
+        Color::new(255, 15, 15, 15),
+    );
+    // scrolling goes up from the newest line
+    let end = session.lines.len().saturating_sub(scroll);
+    let start = end.saturating_sub(visible);
+    let mut line_y = top + 32.0;
+    for line in session.lines.range(start..end) {
+        let text: String = line.text.chars().take(84).collect();
+        let line_color = if line.stderr {
+            Color::new(255, 255, 120, 120)
+        } else {
+            Color::new(255, 200, 200, 200)
+        };
+        drawing.draw_text(&text, Location2::new(323.0, line_y), line_color, 11.0);
+        line_y += 13.0;
+    }
+    drawing.draw_text(
+        &format!("log: {}", session.log_path.display()),
+        Location2::new(320.0, top + 20.0 + box_height + 13.0),
+        Color::new(255, 150, 150, 150),
+        11.0,
+    );
+}
+
+/// Release notes starting at line `scroll`, returns the link that was clicked
+fn draw_notes(
+    drawing: &mut Drawing,
+    lines: &[markdown::Line],
+    scroll: usize,
+    top: f32,
+    visible: usize,
+) -> Option<String> {
+    let size = 12.0;
+    // monospace font, every character has the same advance
+    let advance = size * 0.6;
+    drawing.draw_square(
+        Location4::new(320.0, top, 520.0, visible as f32 * 14.0 + 6.0),
+        Color::new(255, 15, 15, 15),
+    );
+    let mut clicked = None;
+    let mut line_y = top + 15.0;
+    for line in lines.iter().skip(scroll).take(visible) {
+        let mut x = 325.0 + line.indent as f32 * advance;
+        for span in line.spans.iter() {
+            let location = Location2::new(x, line_y);
+            if let Some(link) = &span.link {
+                if drawing.draw_link(&span.text, location, size) {
+                    clicked = Some(link.clone());
+                }
+            } else {
+                match span.style {
+                    Style::Plain => drawing.draw_text(
+                        &span.text,
+                        location,
+                        Color::new(255, 200, 200, 200),
+                        size,
+                    ),
+                    Style::Code => {
+                        drawing.draw_square(
+                            Location4::new(
+                                x,
+                                line_y - size + 1.0,
+                                span.text.chars().count() as f32 * advance,
+                                size + 2.0,
+                            ),
+                            Color::new(255, 45, 45, 45),
+                        );
+                        drawing.draw_text(
+                            &span.text,
+                            location,
+                            Color::new(255, 170, 220, 170),
+                            size,
+                        )
+                    }
+                    Style::Bold | Style::Heading => {
+                        let color = if span.style == Style::Heading {
+                            Color::new(255, 255, 220, 120)
+                        } else {
+                            Color::new(255, 255, 255, 255)
+                        };
+                        // one font weight only, bold is the text drawn twice
+                        drawing.draw_text(&span.text, location, color, size);
+                        drawing.draw_text(&span.text, Location2::new(x + 0.7, line_y), color, size);
+                    }
+                }
+            }
+            x += span.text.chars().count() as f32 * advance;
+        }
+        line_y += 14.0;
+    }
+    clicked
+}
 
-struct Release {
-    name: String,
+fn open_url(state: &Arc<Mutex<State>>, url: &str) {
+    if let Err(e) = webbrowser::open(url) {
+        state.lock().status_message = format!("Cannot open {}: {}", url, e);
+    }
+}
+
+/// `12.3/80.0 MiB 15% 2.30 MiB/s ETA 00:00:30`, without the total and ETA when the length is unknown
+fn progress_text(progress: &download::Progress) -> String {
+    let mib = |bytes: u64| bytes as f64 / 1024.0 / 1024.0;
+    match (progress.total, progress.fraction()) {
+        (Some(total), Some(fraction)) => format!(
+            "{:.1}/{:.1} MiB {:.0}% {:.2} MiB/s ETA {}",
+            mib(progress.received),
+            mib(total),
+            fraction * 100.0,
+            mib(progress.speed() as u64),
+            progress
+                .eta()
+                .map_or_else(|| "--:--:--".to_string(), format_duration)
+        ),
+        _ => format!(
+            "{:.1} MiB {:.2} MiB/s",
+            mib(progress.received),
+            mib(progress.speed() as u64)
+        ),
+    }
+}
+
+/// Filling bar, or a sliding block when the length is unknown
+fn draw_progress_bar(drawing: &mut Drawing, location: Location4, progress: &download::Progress) {
+    drawing.draw_square(location, Color::new(255, 100, 100, 100));
+    match progress.fraction() {
+        Some(fraction) => drawing.draw_square(
+            Location4::new(location.x, location.y, location.w * fraction, location.h),
+            Color::new(255, 110, 200, 110),
+        ),
+        None => {
+            let block = location.w / 7.0;
+            let phase = progress.started.elapsed().as_secs_f32() % 2.0;
+            let position = if phase < 1.0 { phase } else { 2.0 - phase };
+            drawing.draw_square(
+                Location4::new(
+                    location.x + (location.w - block) * position,
+                    location.y,
+                    block,
+                    location.h,
+                ),
+                Color::new(255, 110, 200, 110),
+            );
+        }
+    }
+}
+
+fn download_server(
+    state: Arc<Mutex<State>>,
+    asset: ReleaseAsset,
+    checksum: Option<Checksum>,
     tag_name: String,
-    download_url: String,
-    installed: bool,
-    stable: bool,
+) {
+    state.lock().server_downloading = true;
+    state.lock().status_message = format!("Downloading server {}...", tag_name);
+    thread::spawn(move || {
+        let message = match server::download(
+            &asset.download_url,
+            &tag_name,
+            Some(asset.size),
+            checksum.as_ref(),
+        ) {
+            Ok(jar) => format!("Server {} saved to {}", tag_name, jar.display()),
+            Err(e) => format!("Server download failed: {}", e),
+        };
+        println!("{}", message);
+        let mut state = state.lock();
+        state.status_message = message;
+        state.server_downloading = false;
+    });
 }
 
-struct State {
-    version_loading_error: bool,
-    is_downloading: bool,
-    download_size: f32,
-    filename: String,
+fn install_jre(state: Arc<Mutex<State>>, url: String, checksum: Option<String>) {
+    state.lock().jre_installing = true;
+    state.lock().status_message = format!("Downloading JRE from {}...", url);
+    thread::spawn(move || {
+        let message = match runtimes::install_runtime(&url, checksum.as_deref()) {
+            Ok(runtime) => {
+                let message = format!("Installed {} {}", runtime.vendor, runtime.version);
+                // a reinstall replaces the runtime at the same path
+                let mut state = state.lock();
+                state
+                    .java_runtimes
+                    .retain(|known| known.path != runtime.path);
+                state.java_runtimes.push(runtime);
+                message
+            }
+            Err(e) => format!("JRE install failed: {}", e),
+        };
+        println!("{}", message);
+        let mut state = state.lock();
+        state.status_message = message;
+        state.jre_installing = false;
+    });
+}
+
+fn scan_java(state: Arc<Mutex<State>>, configured: Option<String>) {
+    state.lock().java_scanning = true;
+    thread::spawn(move || {
+        let runtimes = java::discover(configured.as_deref());
+        println!("found {} java runtimes", runtimes.len());
+        let mut state = state.lock();
+        state.java_runtimes = runtimes;
+        state.java_scanning = false;
+    });
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut options = WindowOptions::default();
-    options.resize = false;
+    let options = WindowOptions {
+        resize: false,
+        ..WindowOptions::default()
+    };
 
     let versions = Arc::new(Mutex::new(Vec::new()));
     let state = Arc::new(Mutex::new(State {
         version_loading_error: false,
-        is_downloading: false,
-        download_size: 0.0,
-        filename: String::new(),
+        rate_limited_until: None,
+        versions_loading: false,
+        versions_generation: 0,
+        versions_revision: 0,
+        offline: false,
+        status_message: String::new(),
+        java_runtimes: Vec::new(),
+        java_scanning: false,
+        jre_installing: false,
+        server_downloading: false,
     }));
 
     let mut window = Window::new("MindLaunch 1.0", WIDTH, HEIGHT, options).unwrap();
@@ -64,53 +464,167 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut frames: f32 = 0.;
     let mut offset: f32 = 0.0;
     let mut coldown = false;
+    let mut selected_tag: Option<String> = None;
+    // tags are only unique within a source
+    let mut selected_source: Option<String> = None;
+    let mut tab = Tab::Java;
+    let mut game: Option<GameProcess> = None;
+    let mut console_scroll: usize = 0;
+    let mut crash_checked = false;
+    let mut crash_report: Option<CrashReport> = None;
+    // instance waiting for the user to confirm its deletion
+    let mut delete_instance: Option<String> = None;
+    let mut focus: Option<Focus> = None;
+    let mut filter = ReleaseFilter::default();
+    let mut view = SortedView::default();
+    let mut instance_name_input = String::new();
+    let mut server: Option<GameProcess> = None;
+    let mut server_scroll: usize = 0;
+    let mut server_command_input = String::new();
+    // position in the list of releases that have a server jar
+    let mut server_release: usize = 0;
+    let mut release_notes: Vec<markdown::Line> = Vec::new();
+    let mut notes_scroll: usize = 0;
+    // source and tag `release_notes` were laid out for
+    let mut notes_for: Option<(String, String)> = None;
+    let typed_chars = Arc::new(Mutex::new(String::new()));
+    window.set_input_callback(Box::new(TypedChars(typed_chars.clone())));
+    let mut runtime_selection = RuntimeSelection::load();
 
-    fn load_versions(versions_clone: Arc<Mutex<Vec<Release>>>, state_clone: Arc<Mutex<State>>) {
-        versions_clone.lock().clear();
-        thread::spawn(move || -> Result<(), Error> {
+    fn load_versions(
+        versions_clone: Arc<Mutex<Vec<Release>>>,
+        state_clone: Arc<Mutex<State>>,
+        sources: Vec<ReleaseSource>,
+        paging: releases::Paging,
+        install_dir: String,
+    ) {
+        let generation = {
+            // cleared and bumped together, a page of the old sources can not slip in between
+            let mut versions = versions_clone.lock();
+            versions.clear();
+            let mut state = state_clone.lock();
+            state.version_loading_error = false;
+            state.rate_limited_until = None;
+            state.offline = false;
+            state.versions_loading = true;
+            state.versions_generation += 1;
+            state.versions_revision += 1;
+            state.versions_generation
+        };
+        thread::spawn(move || {
             //thread::sleep_ms(35000);
-            // get stable releases
-            let data: String =
-                http_tools::http_request("https://api.github.com/repos/Anuken/Mindustry/releases")?;
-            let data_json: Result<Vec<models::release::Root>, serde_json::error::Error> =
-                serde_json::from_str(&data);
-            match data_json {
-                Ok(json) => {
-                    for version in json.iter() {
-                        versions_clone.lock().push(Release {
-                            download_url: version.assets[0].clone().browser_download_url.unwrap(),
-                            installed: false,
-                            name: version
-                                .clone()
-                                .name
-                                .unwrap_or("Unknown version string".to_string()),
-                            stable: true,
-                            tag_name: version.clone().tag_name.unwrap_or("Unknown".to_string()),
-                        });
-                    }
-                }
-                Err(_) => {
-                    state_clone.lock().version_loading_error = true;
-                }
-            }
-            // bleeding edge
-            let data: String = http_tools::http_request(
-                "https://api.github.com/repos/Anuken/MindustryBuilds/releases",
-            )?;
-            let data_json: Vec<models::release::Root> = serde_json::from_str(&data).unwrap();
-            for version in data_json.iter() {
-                versions_clone.lock().push(Release {
-                    download_url: version.assets[0].clone().browser_download_url.unwrap(),
-                    installed: false,
-                    name: version
-                        .clone()
-                        .name
-                        .unwrap_or("Bleeding edge build".to_string()),
-                    stable: false,
-                    tag_name: version.clone().tag_name.unwrap_or("Unknown".to_string()),
+            // the cached list is shown right away and replaced once the refresh is done
+            let mut cache = ReleaseCache::load();
+            let sources: Vec<_> = sources
+                .iter()
+                .map(|source| (source, providers::for_source(source)))
+                .collect();
+            let mut jars = installed::scan(&install_dir);
+            let mut database = installed::database();
+            let mut cached: Vec<Release> = sources
+                .iter()
+                .filter_map(|(_, provider)| provider.as_ref().ok())
+                .flat_map(|provider| provider.cached(&cache, paging))
+                .collect();
+            installed::mark(&mut cached, &install_dir, &mut jars, &database);
+            let mut replace = !cached.is_empty();
+            if replace {
+                update_versions(&versions_clone, &state_clone, generation, |versions| {
+                    *versions = cached
+                });
+            }
+
+            loop {
+                let mut fresh = Vec::new();
+                let mut failed = false;
+                let mut offline = false;
+                for (source, provider) in sources.iter() {
+                    // without a cache pages show up in the list as soon as they arrive.
+                    // The whole list is marked again, a jar can only go to one release of all pages.
+                    let mut on_page = |page: Vec<Release>| {
+                        if state_clone.lock().versions_generation != generation {
+                            return false;
+                        }
+                        fresh.extend(page);
+                        installed::mark(&mut fresh, &install_dir, &mut jars, &database);
+                        if replace {
+                            true
+                        } else {
+                            update_versions(&versions_clone, &state_clone, generation, |versions| {
+                                *versions = fresh.clone()
+                            })
+                        }
+                    };
+                    let loaded = match provider {
+                        Ok(provider) => provider.fetch(&mut cache, paging, &mut on_page),
+                        Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+                    };
+                    if let Err(e) = loaded {
+                        println!("cannot load releases from {}: {}", source.name, e);
+                        let mut state = state_clone.lock();
+                        state.version_loading_error = true;
+                        if let Some(limit) = http_tools::rate_limited(&e) {
+                            state.rate_limited_until = Some(limit.reset);
+                            state.status_message = format!(
+                                "GitHub {}, set github_token in Settings.toml to raise the limit",
+                                limit
+                            );
+                        }
+                        offline |= http_tools::is_offline(&e);
+                        failed = true;
+                    }
+                }
+                if let Err(e) = cache.save() {
+                    println!("cannot save release cache: {}", e);
+                }
+                let current =
+                    update_versions(&versions_clone, &state_clone, generation, |versions| {
+                        if replace && !failed {
+                            *versions = fresh;
+                        }
+                    });
+                if !current {
+                    return;
+                }
+                if !offline {
+                    let mut state = state_clone.lock();
+                    if state.offline {
+                        state.offline = false;
+                        state.version_loading_error = failed;
+                        state.status_message = "Back online".to_string();
+                    }
+                    state.versions_loading = false;
+                    return;
+                }
+
+                // the jars on disk stay launchable, versions the list already has are kept
+                let installed = launcher::installed_releases(&install_dir, &database);
+                update_versions(&versions_clone, &state_clone, generation, |versions| {
+                    for release in installed {
+                        if !versions.iter().any(|version| {
+                            version.tag_name == release.tag_name || version.jar == release.jar
+                        }) {
+                            versions.push(release);
+                        }
+                    }
                 });
+                {
+                    let mut state = state_clone.lock();
+                    state.offline = true;
+                    state.versions_loading = false;
+                    state.status_message =
+                        "Offline, installed versions only. Retrying in the background".to_string();
+                }
+                thread::sleep(OFFLINE_RETRY);
+                if state_clone.lock().versions_generation != generation {
+                    return;
+                }
+                // the retry collects everything before swapping, the offline list stays until then
+                replace = true;
+                jars = installed::scan(&install_dir);
+                database = installed::database();
+                state_clone.lock().versions_loading = true;
             }
-            Ok(())
         });
     }
 
@@ -124,9 +638,44 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .merge(config::Environment::with_prefix("APP"))
         .unwrap();
 
-    let mut settings_hash = settings.try_into::<HashMap<String, String>>().unwrap();
+    let settings_hash = settings.try_into::<HashMap<String, String>>().unwrap();
+    let mut instances = InstancesFile::load(settings_hash.get("path").unwrap())?;
+    http_tools::set_github_token(
+        settings_hash
+            .get("github_token")
+            .filter(|token| !token.is_empty())
+            .cloned()
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok()),
+    );
 
-    load_versions(versions.clone(), state.clone());
+    let downloads = DownloadQueue::new(
+        settings_hash
+            .get("max_downloads")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(2),
+    );
+    let paging = releases::Paging {
+        per_page: settings_hash
+            .get("releases_per_page")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(releases::Paging::default().per_page),
+        max_pages: settings_hash
+            .get("releases_max_pages")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(releases::Paging::default().max_pages),
+    };
+    let release_sources = sources::load().unwrap_or_else(|e| {
+        state.lock().status_message = format!("Cannot read {}: {}", sources::SOURCES_FILE, e);
+        sources::default_sources()
+    });
+    load_versions(
+        versions.clone(),
+        state.clone(),
+        release_sources.clone(),
+        paging,
+        instances.current().path.clone(),
+    );
+    scan_java(state.clone(), settings_hash.get("java_path").cloned());
     let size = window.get_size();
     // programm processsing
     while window.is_open() {
@@ -136,7 +685,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let mut drawing = Drawing::new(DrawTarget::new(size.0 as i32, size.1 as i32), &font);
 
         // event processing
-        window.get_mouse_pos(MouseMode::Clamp).map(|mouse| {
+        if let Some(mouse) = window.get_mouse_pos(MouseMode::Clamp) {
             if window.get_mouse_down(MouseButton::Left) && !coldown {
                 drawing.process_mouse(mouse.0, mouse.1);
                 println!("elapsed: {}", drawing.last_click.elapsed().as_secs_f32());
@@ -146,7 +695,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             if (frames % 3.0) <= 0.0 {
                 coldown = false;
             }
-        });
+        }
+
+        let typed = std::mem::take(&mut *typed_chars.lock());
+        drawing.process_keyboard(
+            typed,
+            window.is_key_pressed(Key::Backspace, KeyRepeat::Yes),
+            window.is_key_pressed(Key::Enter, KeyRepeat::No),
+        );
+
+        // crash detection once the game has exited
+        if let Some(game) = &game {
+            let session = game.session.lock();
+            if !crash_checked && !session.running() {
+                crash_checked = true;
+                crash_report = crash::detect(&session);
+            }
+        }
+        // the dialogs are modal, widgets under them must not get the click
+        let download_items = downloads.items();
+        let dialog_mouse = if crash_report.is_some() || delete_instance.is_some() {
+            Some(drawing.take_mouse())
+        } else {
+            None
+        };
 
         // Drawing listbox square
         drawing.draw_square(
@@ -157,30 +729,48 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Drawing play buttons
         {
             let versions_lock = versions.lock();
+            let revision = state.lock().versions_revision;
             drawing.draw_text(
                 "PLAY:",
                 Location2::new(320.0, 15.0),
                 Color::new(255, 255, 255, 255),
                 15.0,
             );
-            if versions_lock.len() > 0 {
-                drawing.draw_button(
-                    &format!("last version {}", versions_lock[0].tag_name),
-                    Location4::new(320.0, 20.0, 200.0, 32.0),
+            for (channel, label, button_y, button_color) in [
+                (
+                    Channel::Stable,
+                    "last version",
+                    20.0,
                     Color::new(255, 100, 120, 100),
-                );
-                {
-                    for version in versions_lock.iter() {
-                        if !version.stable {
-                            drawing.draw_button(
-                                &format!("last bleeding edge {}", version.tag_name),
-                                Location4::new(320.0, 55.0, 200.0, 32.0),
-                                Color::new(255, 120, 100, 100),
-                            );
-                            break;
-                        }
+                ),
+                (
+                    Channel::BleedingEdge,
+                    "last bleeding edge",
+                    55.0,
+                    Color::new(255, 120, 100, 100),
+                ),
+            ]
+            .iter()
+            {
+                let latest = match view.latest(*channel, revision, &versions_lock) {
+                    Some(latest) => latest,
+                    None => continue,
+                };
+                if drawing.draw_button(
+                    &format!("{} {}", label, latest.tag_name),
+                    Location4::new(320.0, *button_y, 200.0, 32.0),
+                    *button_color,
+                ) {
+                    selected_tag = Some(latest.tag_name.clone());
+                    selected_source = Some(latest.source.clone());
+                    let launched =
+                        play_version(instances.current(), latest, &state, &runtime_selection);
+                    if launched.is_some() {
+                        game = launched;
+                        tab = Tab::Console;
+                        console_scroll = 0;
+                        crash_checked = false;
                     }
-                    drop(versions_lock);
                 }
             }
         }
@@ -190,7 +780,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Location4::new(310.0, 450.0, 50.0, 24.0),
             Color::new(255, 100, 110, 100),
         ) {
-            load_versions(versions.clone(), state.clone());
+            load_versions(
+                versions.clone(),
+                state.clone(),
+                release_sources.clone(),
+                paging,
+                instances.current().path.clone(),
+            );
         }
         drawing.draw_text(
             "Click here to change Mindustry installation location: ",
@@ -199,7 +795,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             15.0,
         );
         if drawing.draw_button(
-            &format!("{}", settings_hash.get("path").unwrap()),
+            &format!("{}: {}", instances.selected, instances.current().path),
             Location4::new(365.0, 450.0, 460.0, 24.0),
             Color::new(255, 100, 100, 100),
         ) {
@@ -210,7 +806,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             match result {
                 Response::Okay(file_path) => {
                     println!("File path = {:?}", file_path);
-                    settings_hash.insert("path".to_string(), file_path);
+                    instances.current_mut().path = file_path;
+                    if let Err(e) = instances.save() {
+                        state.lock().status_message = format!("Cannot save instances: {}", e);
+                    }
+                    refresh_installed(
+                    versions.clone(),
+                    state.clone(),
+                    instances.current().path.clone(),
+                );
                 }
                 Response::Cancel => println!("User canceled"),
                 _ => (),
@@ -218,11 +822,664 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         // Drawing options
 
+        // Tabs
+        for (tab_x, tab_name, tab_value) in [
+            (320.0, "Java", Tab::Java),
+            (385.0, "Console", Tab::Console),
+            (450.0, "Instances", Tab::Instances),
+            (515.0, "Server", Tab::Server),
+            (580.0, "Notes", Tab::Notes),
+            (645.0, "Queue", Tab::Queue),
+        ]
+        .iter()
+        {
+            let tab_color = if tab == *tab_value {
+                Color::new(255, 110, 180, 110)
+            } else {
+                Color::new(255, 100, 100, 100)
+            };
+            if drawing.draw_button(
+                tab_name,
+                Location4::new(*tab_x, 95.0, 60.0, 20.0),
+                tab_color,
+            ) {
+                tab = *tab_value;
+            }
+        }
+
+        // Java runtimes
+        if tab == Tab::Java {
+            if drawing.draw_button(
+                "Rescan",
+                Location4::new(780.0, 95.0, 60.0, 20.0),
+                Color::new(255, 100, 110, 100),
+            ) && !state.lock().java_scanning
+            {
+                scan_java(state.clone(), settings_hash.get("java_path").cloned());
+            }
+            if drawing.draw_button(
+                "Get JRE",
+                Location4::new(650.0, 95.0, 60.0, 20.0),
+                Color::new(255, 100, 110, 100),
+            ) && !state.lock().jre_installing
+            {
+                match settings_hash.get("jre_url").filter(|url| !url.is_empty()) {
+                    Some(url) => install_jre(
+                        state.clone(),
+                        url.clone(),
+                        settings_hash.get("jre_sha256").cloned(),
+                    ),
+                    None => {
+                        state.lock().status_message =
+                            "Set jre_url in Settings.toml to download a JRE".to_string()
+                    }
+                }
+            }
+            if drawing.draw_button(
+                "Clean up",
+                Location4::new(715.0, 95.0, 60.0, 20.0),
+                Color::new(255, 100, 110, 100),
+            ) {
+                let message = match runtimes::remove_unused(&runtime_selection, &state.lock().java_runtimes) {
+                    Ok(removed) => format!("Removed {} unused runtimes", removed.len()),
+                    Err(e) => format!("Cannot remove runtimes: {}", e),
+                };
+                state.lock().status_message = message;
+                scan_java(state.clone(), settings_hash.get("java_path").cloned());
+            }
+
+            let state_lock = state.lock();
+            if state_lock.java_scanning {
+                drawing.draw_text(
+                    "Searching for Java runtimes...",
+                    Location2::new(320.0, 135.0),
+                    Color::new(255, 255, 255, 255),
+                    14.0,
+                );
+            } else if state_lock.java_runtimes.is_empty() {
+                drawing.draw_text(
+                    "No Java found, set java_path in Settings.toml",
+                    Location2::new(320.0, 135.0),
+                    Color::new(255, 255, 0, 0),
+                    14.0,
+                );
+            }
+
+            let mut row_y = 120.0;
+            for runtime in state_lock.java_runtimes.iter().take(9) {
+                let is_global = runtime_selection.global.as_ref() == Some(&runtime.path);
+                let runtime_color = if is_global {
+                    Color::new(255, 110, 180, 110)
+                } else {
+                    Color::new(255, 110, 110, 110)
+                };
+                let label: String = format!(
+                    "{} {} ({})",
+                    runtime.vendor,
+                    runtime.version,
+                    runtime.path.display()
+                )
+                .chars()
+                .take(52)
+                .collect();
+                if drawing.draw_button(
+                    &label,
+                    Location4::new(320.0, row_y, 380.0, 20.0),
+                    runtime_color,
+                ) {
+                    runtime_selection.global = Some(runtime.path.clone());
+                    if let Err(e) = runtime_selection.save() {
+                        println!("cannot save java selection: {}", e);
+                    }
+                }
+
+                // binding of the runtime to the last used version
+                if let Some(tag) = &selected_tag {
+                    let bound = runtime_selection.per_version.get(tag) == Some(&runtime.path);
+                    let bind_text = if bound {
+                        format!("unbind {}", tag)
+                    } else {
+                        format!("only for {}", tag)
+                    };
+                    if drawing.draw_button(
+                        &bind_text,
+                        Location4::new(705.0, row_y, 135.0, 20.0),
+                        Color::new(255, 110, 110, 200),
+                    ) {
+                        if bound {
+                            runtime_selection.per_version.remove(tag);
+                        } else {
+                            runtime_selection
+                                .per_version
+                                .insert(tag.clone(), runtime.path.clone());
+                        }
+                        if let Err(e) = runtime_selection.save() {
+                            println!("cannot save java selection: {}", e);
+                        }
+                    }
+                }
+                row_y += 22.0;
+            }
+
+            if let Some(tag) = &selected_tag {
+                let required = java::required_major(tag);
+                if let Some(runtime) = runtime_selection.resolve(tag, &state_lock.java_runtimes) {
+                    if runtime.major < required {
+                        drawing.draw_text(
+                            &format!(
+                                "{} needs Java {}+, selected runtime is Java {}",
+                                tag, required, runtime.major
+                            ),
+                            Location2::new(320.0, row_y + 15.0),
+                            Color::new(255, 255, 0, 0),
+                            14.0,
+                        );
+                    }
+                }
+            }
+        }
+
+        // Instances
+        if tab == Tab::Instances {
+            match drawing.draw_input(
+                &mut instance_name_input,
+                Location4::new(320.0, 120.0, 250.0, 20.0),
+                focus == Some(Focus::InstanceName),
+            ) {
+                InputEvent::Clicked => focus = Some(Focus::InstanceName),
+                InputEvent::Submitted => focus = None,
+                InputEvent::Idle => (),
+            }
+
+            let selected = instances.selected.clone();
+            let new_name = instance_name_input.trim().to_string();
+            let mut result = None;
+            if drawing.draw_button(
+                "New",
+                Location4::new(575.0, 120.0, 60.0, 20.0),
+                Color::new(255, 100, 110, 100),
+            ) {
+                result = Some(instances.create(&new_name));
+            }
+            if drawing.draw_button(
+                "Rename",
+                Location4::new(640.0, 120.0, 60.0, 20.0),
+                Color::new(255, 100, 110, 100),
+            ) {
+                result = Some(instances.rename(&selected, &new_name));
+            }
+            if drawing.draw_button(
+                "Clone",
+                Location4::new(705.0, 120.0, 60.0, 20.0),
+                Color::new(255, 100, 110, 100),
+            ) {
+                result = Some(instances.clone_instance(&selected, &new_name));
+            }
+            if drawing.draw_button(
+                "Delete",
+                Location4::new(770.0, 120.0, 60.0, 20.0),
+                Color::new(255, 200, 110, 110),
+            ) {
+                if instances.instance.len() > 1 {
+                    delete_instance = Some(selected.clone());
+                } else {
+                    // refused with the reason, nothing to confirm
+                    result = Some(instances.delete(&selected));
+                }
+            }
+            match result {
+                Some(Ok(())) => {
+                    instance_name_input.clear();
+                    if let Err(e) = instances.save() {
+                        state.lock().status_message = format!("Cannot save instances: {}", e);
+                    }
+                }
+                Some(Err(e)) => state.lock().status_message = format!("Instance error: {}", e),
+                None => (),
+            }
+
+            let mut row_y = 145.0;
+            let mut clicked = None;
+            for instance in instances.instance.iter().take(10) {
+                let instance_color = if instance.name == instances.selected {
+                    Color::new(255, 110, 180, 110)
+                } else {
+                    Color::new(255, 110, 110, 110)
+                };
+                let label: String = format!("{} - {}", instance.name, instance.path)
+                    .chars()
+                    .take(68)
+                    .collect();
+                if drawing.draw_button(
+                    &label,
+                    Location4::new(320.0, row_y, 520.0, 20.0),
+                    instance_color,
+                ) {
+                    clicked = Some(instance.name.clone());
+                }
+                row_y += 22.0;
+            }
+            if let Some(name) = clicked {
+                instances.select(&name);
+                if let Err(e) = instances.save() {
+                    state.lock().status_message = format!("Cannot save instances: {}", e);
+                }
+                refresh_installed(
+                    versions.clone(),
+                    state.clone(),
+                    instances.current().path.clone(),
+                );
+            }
+        }
+
+        // Game console
+        if tab == Tab::Console {
+            match &game {
+                Some(game) => {
+                    let session = game.session.lock();
+                    draw_session(&mut drawing, &session, console_scroll, 118.0, 17);
+                    let running = session.running();
+                    drop(session);
+
+                    if running
+                        && drawing.draw_button(
+                            "Kill",
+                            Location4::new(780.0, 118.0, 60.0, 18.0),
+                            Color::new(255, 200, 90, 90),
+                        )
+                    {
+                        if let Err(e) = game.kill() {
+                            state.lock().status_message = format!("Cannot kill the game: {}", e);
+                        }
+                    }
+                }
+                None => {
+                    drawing.draw_text(
+                        "The game is not running, press PLAY",
+                        Location2::new(320.0, 135.0),
+                        Color::new(255, 255, 255, 255),
+                        14.0,
+                    );
+                }
+            }
+        }
+
+        // Download queue
+        if tab == Tab::Queue {
+            if drawing.draw_button(
+                "Clear",
+                Location4::new(780.0, 95.0, 60.0, 20.0),
+                Color::new(255, 100, 110, 100),
+            ) {
+                downloads.clear_finished();
+            }
+            if download_items.is_empty() {
+                drawing.draw_text(
+                    "Nothing is downloading, press INSTALL on a version",
+                    Location2::new(320.0, 135.0),
+                    Color::new(255, 255, 255, 255),
+                    14.0,
+                );
+            }
+            let mut row_y = 120.0;
+            for item in download_items.iter().take(10) {
+                let (status, status_color) = match &item.status {
+                    ItemStatus::Queued => ("queued".to_string(), Color::new(255, 200, 200, 200)),
+                    ItemStatus::Running => (
+                        item.progress
+                            .as_ref()
+                            .map_or_else(|| "starting".to_string(), progress_text),
+                        Color::new(255, 255, 255, 255),
+                    ),
+                    ItemStatus::Paused => ("paused".to_string(), Color::new(255, 220, 220, 120)),
+                    ItemStatus::Done => ("done".to_string(), Color::new(255, 120, 220, 120)),
+                    ItemStatus::Failed(e) => {
+                        (format!("failed: {}", e), Color::new(255, 255, 90, 90))
+                    }
+                    ItemStatus::Cancelled => {
+                        ("cancelled".to_string(), Color::new(255, 150, 150, 150))
+                    }
+                };
+                let label: String = item.label.chars().take(14).collect();
+                drawing.draw_text(
+                    &label,
+                    Location2::new(320.0, row_y + 13.0),
+                    Color::new(255, 255, 255, 255),
+                    13.0,
+                );
+                let status: String = status.chars().take(48).collect();
+                drawing.draw_text(
+                    &status,
+                    Location2::new(430.0, row_y + 13.0),
+                    status_color,
+                    12.0,
+                );
+                if let Some(progress) = &item.progress {
+                    if item.status == ItemStatus::Running || item.status == ItemStatus::Paused {
+                        draw_progress_bar(
+                            &mut drawing,
+                            Location4::new(430.0, row_y + 17.0, 280.0, 3.0),
+                            progress,
+                        );
+                    }
+                }
+                let action = match item.status {
+                    ItemStatus::Queued | ItemStatus::Running => Some("Pause"),
+                    ItemStatus::Paused => Some("Resume"),
+                    ItemStatus::Failed(_) => Some("Retry"),
+                    ItemStatus::Done | ItemStatus::Cancelled => None,
+                };
+                if let Some(action) = action {
+                    if drawing.draw_button(
+                        action,
+                        Location4::new(715.0, row_y, 60.0, 18.0),
+                        Color::new(255, 100, 110, 100),
+                    ) {
+                        match item.status {
+                            ItemStatus::Queued | ItemStatus::Running => downloads.pause(item.id),
+                            _ => downloads.resume(item.id),
+                        }
+                    }
+                }
+                let active = !matches!(item.status, ItemStatus::Done | ItemStatus::Cancelled);
+                if active
+                    && drawing.draw_button(
+                        "Cancel",
+                        Location4::new(780.0, row_y, 60.0, 18.0),
+                        Color::new(255, 200, 110, 110),
+                    )
+                {
+                    downloads.cancel(item.id);
+                }
+                row_y += 26.0;
+            }
+        }
+
+        // Release notes
+        if tab == Tab::Notes {
+            let selected = selected_tag.as_ref().and_then(|tag| {
+                versions
+                    .lock()
+                    .iter()
+                    .find(|version| {
+                        &version.tag_name == tag
+                            && Some(&version.source) == selected_source.as_ref()
+                    })
+                    .cloned()
+            });
+            match selected {
+                Some(release) => {
+                    // any click that selects a version can change the notes, not only "Notes"
+                    let key = (release.source.clone(), release.tag_name.clone());
+                    if notes_for.as_ref() != Some(&key) {
+                        release_notes =
+                            markdown::layout(release.body.as_deref().unwrap_or(""), NOTES_WIDTH);
+                        notes_scroll = 0;
+                        notes_for = Some(key);
+                    }
+                    let title: String = format!("{} - {}", release.name, release.tag_name)
+                        .chars()
+                        .take(40)
+                        .collect();
+                    drawing.draw_text(
+                        &title,
+                        Location2::new(320.0, 132.0),
+                        Color::new(255, 255, 255, 255),
+                        13.0,
+                    );
+                    if drawing.draw_button(
+                        "Play",
+                        Location4::new(650.0, 118.0, 60.0, 18.0),
+                        Color::new(255, 100, 120, 100),
+                    ) {
+                        let launched =
+                            play_version(instances.current(), &release, &state, &runtime_selection);
+                        if launched.is_some() {
+                            game = launched;
+                            tab = Tab::Console;
+                            console_scroll = 0;
+                            crash_checked = false;
+                        }
+                    }
+                    if let Some(html_url) = &release.html_url {
+                        let open_text = if html_url.contains("github.com") {
+                            "Open on GitHub"
+                        } else {
+                            "Open in browser"
+                        };
+                        if drawing.draw_button(
+                            open_text,
+                            Location4::new(715.0, 118.0, 125.0, 18.0),
+                            Color::new(255, 110, 110, 200),
+                        ) {
+                            open_url(&state, html_url);
+                        }
+                    }
+                    if release_notes.is_empty() {
+                        drawing.draw_text(
+                            "No release notes",
+                            Location2::new(320.0, 155.0),
+                            Color::new(255, 150, 150, 150),
+                            13.0,
+                        );
+                    } else if let Some(link) =
+                        draw_notes(&mut drawing, &release_notes, notes_scroll, 140.0, 17)
+                    {
+                        open_url(&state, &link);
+                    }
+                }
+                None => {
+                    drawing.draw_text(
+                        "Click a version in the list to see its release notes",
+                        Location2::new(320.0, 135.0),
+                        Color::new(255, 255, 255, 255),
+                        14.0,
+                    );
+                }
+            }
+        }
+
+        // Dedicated server
+        if tab == Tab::Server {
+            let server_releases: Vec<(String, ReleaseAsset, Option<Checksum>)> = versions
+                .lock()
+                .iter()
+                .filter_map(|version| {
+                    version.server.as_ref().map(|asset| {
+                        (
+                            version.tag_name.clone(),
+                            asset.clone(),
+                            Checksum::of(asset, &version.assets),
+                        )
+                    })
+                })
+                .collect();
+            server_release = server_release.min(server_releases.len().saturating_sub(1));
+            let running = server
+                .as_ref()
+                .is_some_and(|server| server.session.lock().running());
+
+            match server_releases.get(server_release) {
+                Some((tag, asset, checksum)) => {
+                    if drawing.draw_button(
+                        "<",
+                        Location4::new(320.0, 118.0, 20.0, 18.0),
+                        Color::new(255, 100, 100, 100),
+                    ) {
+                        server_release = server_release.saturating_sub(1);
+                    }
+                    drawing.draw_text(
+                        tag,
+                        Location2::new(345.0, 132.0),
+                        Color::new(255, 255, 255, 255),
+                        13.0,
+                    );
+                    if drawing.draw_button(
+                        ">",
+                        Location4::new(450.0, 118.0, 20.0, 18.0),
+                        Color::new(255, 100, 100, 100),
+                    ) {
+                        server_release += 1;
+                    }
+
+                    let downloaded = server::server_jar(tag).is_file();
+                    if drawing.draw_button(
+                        if downloaded { "Update" } else { "Download" },
+                        Location4::new(520.0, 118.0, 60.0, 18.0),
+                        Color::new(255, 110, 110, 200),
+                    ) && !state.lock().server_downloading
+                        && !running
+                    {
+                        download_server(
+                            state.clone(),
+                            asset.clone(),
+                            checksum.clone(),
+                            tag.clone(),
+                        );
+                    }
+                    if !running
+                        && drawing.draw_button(
+                            "Start",
+                            Location4::new(585.0, 118.0, 60.0, 18.0),
+                            Color::new(255, 100, 120, 100),
+                        )
+                    {
+                        let runtime = runtime_selection
+                            .resolve(tag, &state.lock().java_runtimes)
+                            .cloned();
+                        match server::start(runtime.as_ref(), tag) {
+                            Ok(started) => {
+                                state.lock().status_message = format!(
+                                    "Started server {} (pid {})",
+                                    tag,
+                                    started.session.lock().pid
+                                );
+                                server = Some(started);
+                                server_scroll = 0;
+                            }
+                            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                                state.lock().status_message =
+                                    format!("Server {} is not downloaded yet", tag)
+                            }
+                            Err(e) => {
+                                state.lock().status_message =
+                                    format!("Failed to start server {}: {}", tag, e)
+                            }
+                        }
+                    }
+                }
+                None => {
+                    drawing.draw_text(
+                        "No release with a server jar",
+                        Location2::new(320.0, 132.0),
+                        Color::new(255, 255, 255, 255),
+                        13.0,
+                    );
+                }
+            }
+
+            if let Some(server) = &server {
+                if running {
+                    if drawing.draw_button(
+                        "Exit",
+                        Location4::new(650.0, 118.0, 60.0, 18.0),
+                        Color::new(255, 200, 110, 110),
+                    ) {
+                        if let Err(e) = server.send_line("exit") {
+                            state.lock().status_message = format!("Cannot stop the server: {}", e);
+                        }
+                    }
+                    if drawing.draw_button(
+                        "Kill",
+                        Location4::new(780.0, 118.0, 60.0, 18.0),
+                        Color::new(255, 200, 90, 90),
+                    ) {
+                        if let Err(e) = server.kill() {
+                            state.lock().status_message = format!("Cannot kill the server: {}", e);
+                        }
+                    }
+                }
+                draw_session(
+                    &mut drawing,
+                    &server.session.lock(),
+                    server_scroll,
+                    142.0,
+                    14,
+                );
+
+                // commands like `host`, `status` or `stop` go to the server input
+                let mut send = false;
+                match drawing.draw_input(
+                    &mut server_command_input,
+                    Location4::new(320.0, 365.0, 455.0, 18.0),
+                    focus == Some(Focus::ServerCommand),
+                ) {
+                    InputEvent::Clicked => focus = Some(Focus::ServerCommand),
+                    InputEvent::Submitted => send = true,
+                    InputEvent::Idle => (),
+                }
+                if drawing.draw_button(
+                    "Send",
+                    Location4::new(780.0, 365.0, 60.0, 18.0),
+                    Color::new(255, 100, 110, 100),
+                ) {
+                    send = true;
+                }
+                let command = server_command_input.trim().to_string();
+                if send && running && !command.is_empty() {
+                    match server.send_line(&command) {
+                        Ok(()) => {
+                            server_command_input.clear();
+                            server_scroll = 0;
+                        }
+                        Err(e) => {
+                            state.lock().status_message = format!("Cannot send command: {}", e)
+                        }
+                    }
+                }
+            }
+        }
+
         // offset of scrllbox
-        let mut idx: f32 = -27.0;
+        let mut idx: f32 = LIST_TOP - 27.0;
+
+        if versions.lock().is_empty() {
+            let rate_limited_until = state.lock().rate_limited_until;
+            if let Some(reset) = rate_limited_until {
+                drawing.draw_text(
+                    &format!("Rate limited until {}", reset.format("%H:%M")),
+                    Location2::new(15.0, 240.0),
+                    Color::new(255, 255, 0, 0),
+                    14.0,
+                );
 
-        if versions.lock().len() <= 0 {
-            if state.lock().version_loading_error {
+                if drawing.draw_button(
+                    "Try again",
+                    Location4::new(25.0, 260.0, 60.0, 24.0),
+                    Color::new(255, 100, 120, 100),
+                ) {
+                    load_versions(
+                        versions.clone(),
+                        state.clone(),
+                        release_sources.clone(),
+                        paging,
+                        instances.current().path.clone(),
+                    );
+                }
+            } else if state.lock().offline {
+                drawing.draw_text(
+                    "Offline, no versions installed",
+                    Location2::new(40.0, 240.0),
+                    Color::new(255, 255, 0, 0),
+                    14.0,
+                );
+                drawing.draw_text(
+                    "Retrying in the background...",
+                    Location2::new(45.0, 260.0),
+                    Color::new(255, 255, 255, 255),
+                    13.0,
+                );
+            } else if state.lock().version_loading_error {
                 drawing.draw_text(
                     "Version loading ERROR please try later",
                     Location2::new(15.0, 240.0),
@@ -235,7 +1492,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     Location4::new(25.0, 260.0, 60.0, 24.0),
                     Color::new(255, 100, 120, 100),
                 ) {
-                    load_versions(versions.clone(), state.clone());
+                    load_versions(
+                        versions.clone(),
+                        state.clone(),
+                        release_sources.clone(),
+                        paging,
+                        instances.current().path.clone(),
+                    );
                 }
             } else {
                 drawing.draw_text(
@@ -246,70 +1509,230 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 );
             }
         } else {
-            for version in versions.lock().iter() {
-                let mut release_color = Color::new(255, 110, 110, 110); // if stable
-                if !version.stable {
-                    release_color = Color::new(255, 180, 110, 110); // bleeding edge
-                }
+            let versions_lock = versions.lock();
+            let revision = state.lock().versions_revision;
+            let shown = view.shown(&filter, revision, &versions_lock);
+            if shown.is_empty() {
+                drawing.draw_text(
+                    "No versions match the filter",
+                    Location2::new(55.0, 240.0),
+                    Color::new(255, 255, 255, 255),
+                    14.0,
+                );
+            }
+            for version in shown {
+                let [red, green, blue] = release_sources
+                    .iter()
+                    .find(|source| source.name == version.source)
+                    .map_or([110, 110, 110], |source| source.color);
+                let release_color = Color::new(255, red, green, blue);
                 idx += 27.0; // offset of list
                              // version badge
-                let baseloc = Location4::new(5., idx + offset, 200.0, 25.0);
-                drawing.draw_button(
+                let baseloc = Location4::new(5., idx + offset, 160.0, 25.0);
+                // rows under the search bar must not take its clicks
+                if baseloc.y < LIST_TOP {
+                    continue;
+                }
+                if drawing.draw_button(
                     &format!("{} - {}", version.name, version.tag_name),
                     baseloc,
                     release_color,
-                );
+                ) {
+                    selected_tag = Some(version.tag_name.clone());
+                    selected_source = Some(version.source.clone());
+                    let launched =
+                        play_version(instances.current(), version, &state, &runtime_selection);
+                    if launched.is_some() {
+                        game = launched;
+                        tab = Tab::Console;
+                        console_scroll = 0;
+                        crash_checked = false;
+                    }
+                }
+                // release notes
+                if drawing.draw_button(
+                    "Notes",
+                    Location4::new(baseloc.x + 160.0, baseloc.y, 40.0, 25.0),
+                    Color::new(255, 90, 90, 130),
+                ) {
+                    selected_tag = Some(version.tag_name.clone());
+                    selected_source = Some(version.source.clone());
+                    tab = Tab::Notes;
+                }
                 //install button
                 let baseloc_install = Location4::new(baseloc.x + 200.0, baseloc.y, 50.0, 25.0);
-                if drawing.draw_button("INSTALL", baseloc_install, Color::new(255, 110, 110, 200)) {
-                    let download_url = version.download_url.clone();
-                    let version_string = version.name.clone();
-                    let version_tag = version.tag_name.clone();
-
-                    if !state.lock().is_downloading {
-                        {
-                            let state = state.clone();
-
-                            thread::spawn(move || -> Result<(), std::io::Error> {
-                                println!("Starting reader...");
-                                state.lock().is_downloading = true;
-
-                                let response = ureq::get(download_url.as_str())
-                                    .set("Transfer-Encoding", "chunked")
-                                    .timeout_connect(5_000)
-                                    .timeout_read(1_000)
-                                    .call();
-
-
-                                println!("Got response!");
-
-                                state.lock().filename = format!("{}.jar", version_string);
-
-                                let len = response
-                                    .header("content-length")
-                                    .unwrap_or("0")
-                                    .parse::<i32>()
-                                    .unwrap();
-                                
-                                let mut buffer = File::create(state.lock().filename.clone())?;
-
-                                println!("Done computing");
-
-                                let mut reader = response.into_reader();
-
-                                println!("Copying");
-                                io::copy(&mut reader, &mut buffer)?;
-
-                                println!("Done!");
-                                state.lock().is_downloading = false;
-                                Ok(())
-                            });
+                // releases without a desktop jar are listed but can not be installed
+                let install_color = if version.desktop.is_some() {
+                    Color::new(255, 110, 110, 200)
+                } else {
+                    Color::new(255, 90, 90, 90)
+                };
+                let jar = launcher::jar_path(
+                    &instances.current().path,
+                    &version.source,
+                    &version.tag_name,
+                );
+                let install_text = match download_items.iter().find(|item| item.target == jar) {
+                    Some(item) if item.status == ItemStatus::Running => item
+                        .progress
+                        .and_then(|progress| progress.fraction())
+                        .map_or_else(
+                            || "...".to_string(),
+                            |fraction| format!("{:.0}%", fraction * 100.0),
+                        ),
+                    Some(item) if item.status == ItemStatus::Queued => "QUEUED".to_string(),
+                    Some(item) if item.status == ItemStatus::Paused => "PAUSED".to_string(),
+                    // a corrupted download was removed, installing again starts over
+                    Some(item) if matches!(item.status, ItemStatus::Failed(_)) => {
+                        "RETRY".to_string()
+                    }
+                    _ if version.desktop.is_some() => "INSTALL".to_string(),
+                    _ => "N/A".to_string(),
+                };
+                if version.installed {
+                    if drawing.draw_button("PLAY", baseloc_install, Color::new(255, 100, 160, 100))
+                    {
+                        selected_tag = Some(version.tag_name.clone());
+                        selected_source = Some(version.source.clone());
+                        let launched =
+                            play_version(instances.current(), version, &state, &runtime_selection);
+                        if launched.is_some() {
+                            game = launched;
+                            tab = Tab::Console;
+                            console_scroll = 0;
+                            crash_checked = false;
                         }
                     }
+                } else if drawing.draw_button(&install_text, baseloc_install, install_color) {
+                    if let Some(asset) = &version.desktop {
+                        let version_tag = version.tag_name.clone();
+                        let install_dir = instances.current().path.clone();
+                        let release = version.clone();
+                        let source = release_sources
+                            .iter()
+                            .find(|source| source.name == version.source)
+                            .cloned();
+                        let versions = versions.clone();
+                        let list_state = state.clone();
+                        // local directory sources hand out `file://` URLs
+                        let queued = downloads.push(
+                            &version.tag_name,
+                            &asset.download_url,
+                            jar.clone(),
+                            Some(asset.size),
+                            Checksum::of(asset, &version.assets),
+                            Box::new(move |jar| {
+                                if let Err(e) =
+                                    installed::record_install(&release, source.as_ref(), jar)
+                                {
+                                    println!("cannot record install of {}: {}", version_tag, e);
+                                }
+                                refresh_installed(versions, list_state, install_dir);
+                            }),
+                        );
+                        state.lock().status_message = match queued {
+                            Some(_) => format!("Queued {}", version.tag_name),
+                            None => format!("{} is already downloading", version.tag_name),
+                        };
+                    } else {
+                        state.lock().status_message = format!(
+                            "{} has no desktop jar, it is not installable",
+                            version.tag_name
+                        );
+                    }
                 }
                 //unistall
                 let baseloc_install = Location4::new(baseloc.x + 250.0, baseloc.y, 50.0, 25.0);
-                drawing.draw_button("DELETE", baseloc_install, Color::new(255, 200, 110, 200));
+                // nothing to delete for versions that are not on disk
+                let delete_color = if version.installed {
+                    Color::new(255, 200, 110, 200)
+                } else {
+                    Color::new(255, 90, 90, 90)
+                };
+                if drawing.draw_button("DELETE", baseloc_install, delete_color) {
+                    if let Some(jar) = version.jar.as_ref().filter(|_| version.installed) {
+                        let message = match fs::remove_file(jar) {
+                            Ok(()) => {
+                                if let Err(e) = installed::forget(jar) {
+                                    println!("cannot update the install database: {}", e);
+                                }
+                                format!("Deleted {}", version.tag_name)
+                            }
+                            Err(e) => format!("Cannot delete {}: {}", jar.display(), e),
+                        };
+                        state.lock().status_message = message;
+                        refresh_installed(
+                    versions.clone(),
+                    state.clone(),
+                    instances.current().path.clone(),
+                );
+                    }
+                }
+            }
+            if state.lock().versions_loading {
+                drawing.draw_text(
+                    "Loading more versions...",
+                    Location2::new(15.0, idx + offset + 42.0),
+                    Color::new(255, 255, 255, 255),
+                    13.0,
+                );
+            }
+        }
+
+        // Search and filter bar, drawn over the list so rows scroll under it
+        {
+            let filter_before = filter.clone();
+            drawing.draw_square(
+                Location4::new(5., 0., 300., LIST_TOP - 2.0),
+                Color::new(255, 40, 40, 40),
+            );
+            match drawing.draw_input(
+                &mut filter.query,
+                Location4::new(8.0, 4.0, 216.0, 18.0),
+                focus == Some(Focus::Search),
+            ) {
+                InputEvent::Clicked => focus = Some(Focus::Search),
+                InputEvent::Submitted => focus = None,
+                InputEvent::Idle => (),
+            }
+            if filter.query.is_empty() && focus != Some(Focus::Search) {
+                drawing.draw_text(
+                    "Search versions...",
+                    Location2::new(11.0, 17.0),
+                    Color::new(255, 70, 70, 70),
+                    12.5,
+                );
+            }
+            if drawing.draw_button(
+                filter.sort.label(),
+                Location4::new(227.0, 4.0, 70.0, 18.0),
+                Color::new(255, 110, 110, 200),
+            ) {
+                filter.sort = filter.sort.next();
+            }
+            for (toggle_x, label, toggle) in [
+                (8.0, "Stable", &mut filter.stable),
+                (81.0, "Bleeding", &mut filter.bleeding_edge),
+                (154.0, "Installed", &mut filter.installed_only),
+                (227.0, "Pre", &mut filter.prerelease),
+            ]
+            .iter_mut()
+            {
+                let toggle_color = if **toggle {
+                    Color::new(255, 100, 130, 100)
+                } else {
+                    Color::new(255, 70, 70, 70)
+                };
+                if drawing.draw_button(
+                    label,
+                    Location4::new(*toggle_x, 26.0, 70.0, 18.0),
+                    toggle_color,
+                ) {
+                    **toggle = !**toggle;
+                }
+            }
+            if filter != filter_before {
+                offset = 0.0;
             }
         }
 
@@ -324,31 +1747,197 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Color::new(255, 110, 110, 110),
         );
 
-        // Download progress
+        if state.lock().offline {
+            drawing.draw_square(
+                Location4::new(770.0, 4.0, 70.0, 20.0),
+                Color::new(255, 170, 60, 60),
+            );
+            drawing.draw_text(
+                "OFFLINE",
+                Location2::new(779.0, 19.0),
+                Color::new(255, 255, 255, 255),
+                14.0,
+            );
+        }
+
+        // Status line
         {
-            let state_clone = state.clone();
-            match state_clone.try_lock() {
-                Some(value) => {
-                    let metadata = fs::metadata(value.filename.clone())?;
+            let status_message = state.lock().status_message.clone();
+            drawing.draw_text(
+                &status_message,
+                Location2::new(310.0, 395.0),
+                Color::new(255, 255, 255, 255),
+                14.0,
+            );
+        }
 
-                    drawing.draw_text(
-                        &format!(
-                            "Downloading... {} MiB/{} MiB",
-                            metadata.len() / 1024 / 1024, value.download_size
-                        ),
-                        Location2::new(310.0, 410.0),
-                        Color::new(255, 255, 255, 255),
-                        15.0,
-                    );
-                }
-                None => {
-                    println!("не удалось заблочить =(");
-                }
+        // Download progress of the first running item, the queue tab has all of them
+        {
+            let running = download_items
+                .iter()
+                .filter(|item| item.status == ItemStatus::Running)
+                .collect::<Vec<_>>();
+            if let Some((item, progress)) = running
+                .first()
+                .and_then(|item| Some((item, item.progress?)))
+            {
+                let more = if running.len() > 1 {
+                    format!(" (+{})", running.len() - 1)
+                } else {
+                    String::new()
+                };
+                drawing.draw_text(
+                    &format!(
+                        "Downloading {}{}: {}",
+                        item.label,
+                        more,
+                        progress_text(&progress)
+                    ),
+                    Location2::new(310.0, 410.0),
+                    Color::new(255, 255, 255, 255),
+                    14.0,
+                );
+                draw_progress_bar(
+                    &mut drawing,
+                    Location4::new(310.0, 420.0, 540.0, 5.0),
+                    &progress,
+                );
             }
+        }
+
+        // Crash dialog
+        if let Some(mouse) = dialog_mouse {
+            drawing.process_mouse(mouse.0, mouse.1);
+        }
+        if let Some(report) = &crash_report {
+            drawing.draw_square(
+                Location4::new(0.0, 0.0, WIDTH as f32, HEIGHT as f32),
+                Color::new(180, 0, 0, 0),
+            );
             drawing.draw_square(
-                Location4::new(310.0, 420.0, 540.0, 5.0),
+                Location4::new(60.0, 40.0, 734.0, 400.0),
+                Color::new(255, 45, 35, 35),
+            );
+            let title = match report.exit_code {
+                Some(code) => format!("Mindustry {} crashed (exit code {})", report.tag_name, code),
+                None => format!("Mindustry {} crashed", report.tag_name),
+            };
+            drawing.draw_text(
+                &title,
+                Location2::new(75.0, 65.0),
+                Color::new(255, 255, 90, 90),
+                16.0,
+            );
+            drawing.draw_text(
+                report
+                    .exception
+                    .as_deref()
+                    .unwrap_or("No Java exception found in the output"),
+                Location2::new(75.0, 90.0),
+                Color::new(255, 255, 255, 255),
+                13.0,
+            );
+            let mut line_y = 110.0;
+            for line in report.stack_trace.iter().take(19) {
+                let text: String = line.chars().take(110).collect();
+                drawing.draw_text(
+                    &text,
+                    Location2::new(85.0, line_y),
+                    Color::new(255, 200, 200, 200),
+                    11.0,
+                );
+                line_y += 14.0;
+            }
+            if let Some(crash_file) = &report.crash_file {
+                drawing.draw_text(
+                    &format!("crash file: {}", crash_file.display()),
+                    Location2::new(75.0, 385.0),
+                    Color::new(255, 150, 150, 150),
+                    11.0,
+                );
+            }
+
+            if drawing.draw_button(
+                "Create crash report",
+                Location4::new(75.0, 400.0, 140.0, 24.0),
+                Color::new(255, 110, 110, 200),
+            ) {
+                let message = match crash::bundle(report) {
+                    Ok(path) => format!("Crash report saved to {}", path.display()),
+                    Err(e) => format!("Cannot create crash report: {}", e),
+                };
+                println!("{}", message);
+                state.lock().status_message = message;
+                crash_report = None;
+            } else if drawing.draw_button(
+                "Close",
+                Location4::new(720.0, 400.0, 60.0, 24.0),
                 Color::new(255, 100, 100, 100),
-            )
+            ) {
+                crash_report = None;
+            }
+        }
+
+        // Delete confirmation, shown after a crash report was dealt with
+        if let (None, Some(name)) = (&crash_report, delete_instance.clone()) {
+            let path = instances
+                .find(&name)
+                .map_or_else(String::new, |instance| instance.path.clone());
+            drawing.draw_square(
+                Location4::new(0.0, 0.0, WIDTH as f32, HEIGHT as f32),
+                Color::new(180, 0, 0, 0),
+            );
+            drawing.draw_square(
+                Location4::new(200.0, 170.0, 454.0, 120.0),
+                Color::new(255, 45, 35, 35),
+            );
+            drawing.draw_text(
+                &format!("Delete instance {}?", name),
+                Location2::new(215.0, 195.0),
+                Color::new(255, 255, 90, 90),
+                16.0,
+            );
+            let detail = if instances.is_managed(&name) {
+                "Its saves, mods and jars are deleted:"
+            } else {
+                "Only the entry is removed, the directory stays:"
+            };
+            drawing.draw_text(
+                detail,
+                Location2::new(215.0, 220.0),
+                Color::new(255, 255, 255, 255),
+                13.0,
+            );
+            let path_text: String = path.chars().take(62).collect();
+            drawing.draw_text(
+                &path_text,
+                Location2::new(215.0, 238.0),
+                Color::new(255, 200, 200, 200),
+                11.0,
+            );
+            if drawing.draw_button(
+                "Delete",
+                Location4::new(215.0, 255.0, 60.0, 24.0),
+                Color::new(255, 200, 110, 110),
+            ) {
+                delete_instance = None;
+                let deleted = instances.delete(&name).and_then(|_| instances.save());
+                state.lock().status_message = match deleted {
+                    Ok(()) => format!("Deleted instance {}", name),
+                    Err(e) => format!("Instance error: {}", e),
+                };
+                refresh_installed(
+                    versions.clone(),
+                    state.clone(),
+                    instances.current().path.clone(),
+                );
+            } else if drawing.draw_button(
+                "Cancel",
+                Location4::new(580.0, 255.0, 60.0, 24.0),
+                Color::new(255, 100, 100, 100),
+            ) {
+                delete_instance = None;
+            }
         }
 
         // lock ~60 FPS
@@ -369,9 +1958,38 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             12.0,
         );
 
-        window.get_scroll_wheel().map(|scroll| {
-            offset += scroll.1 * 4.0;
-        });
+        if let Some(scroll) = window.get_scroll_wheel() {
+            let over_panel = window
+                .get_mouse_pos(MouseMode::Clamp)
+                .is_some_and(|mouse| mouse.0 > 310.0);
+            if over_panel && tab == Tab::Notes {
+                // notes scroll from the top, unlike the consoles
+                if scroll.1 > 0.0 {
+                    notes_scroll = notes_scroll.saturating_sub(3);
+                } else if scroll.1 < 0.0 {
+                    notes_scroll = (notes_scroll + 3).min(release_notes.len().saturating_sub(1));
+                }
+            } else {
+                let scrolled = match tab {
+                    Tab::Console => Some((&game, &mut console_scroll)),
+                    Tab::Server => Some((&server, &mut server_scroll)),
+                    _ => None,
+                };
+                match scrolled {
+                    Some((process, console_scroll)) if over_panel => {
+                        let lines = process
+                            .as_ref()
+                            .map_or(0, |process| process.session.lock().lines.len());
+                        if scroll.1 > 0.0 {
+                            *console_scroll = (*console_scroll + 3).min(lines);
+                        } else if scroll.1 < 0.0 {
+                            *console_scroll = console_scroll.saturating_sub(3);
+                        }
+                    }
+                    _ => offset += scroll.1 * 4.0,
+                }
+            }
+        }
 
         let size = window.get_size();
         window
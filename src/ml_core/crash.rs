@@ -0,0 +1,297 @@
+use super::download;
+use super::paths;
+use super::process::Session;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use zip::write::FileOptions;
+
+/// How many stack frames the crash dialog keeps
+const MAX_TRACE_LINES: usize = 40;
+
+pub struct CrashReport {
+    pub tag_name: String,
+    pub exit_code: Option<i32>,
+    /// First line of the Java exception, e.g. `java.lang.NullPointerException: ...`
+    pub exception: Option<String>,
+    pub stack_trace: Vec<String>,
+    pub crash_file: Option<PathBuf>,
+    pub log_path: PathBuf,
+    pub profile: String,
+    pub java: String,
+}
+
+pub fn reports_dir() -> PathBuf {
+    paths::data_dir().join("crash-reports")
+}
+
+/// Newest file in `<data dir>/crashes` written after `since`
+pub fn new_crash_file(data_dir: &Path, since: SystemTime) -> Option<PathBuf> {
+    fs::read_dir(data_dir.join("crashes"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            if modified >= since {
+                Some((modified, entry.path()))
+            } else {
+                None
+            }
+        })
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// Drops the `[E] ` level tag Mindustry puts in front of log lines
+fn strip_log_tag(line: &str) -> &str {
+    let line = line.trim();
+    if line.starts_with('[') {
+        if let Some(end) = line.find("] ") {
+            return line[end + 2..].trim();
+        }
+    }
+    line
+}
+
+/// `java.lang.RuntimeException: text`, `Exception in thread "main" ...` or `Caused by: ...`
+fn is_exception_line(line: &str) -> bool {
+    let line = strip_log_tag(line);
+    if line.starts_with("Exception in thread") || line.starts_with("Caused by:") {
+        return true;
+    }
+    let class = line.split(':').next().unwrap_or("");
+    !class.contains(' ')
+        && class.contains('.')
+        && (class.ends_with("Exception") || class.ends_with("Error"))
+}
+
+fn is_trace_line(line: &str) -> bool {
+    let line = strip_log_tag(line);
+    line.starts_with("at ") || line.starts_with("Caused by:") || line.starts_with("...")
+}
+
+/// Picks the first Java exception and the stack trace that follows it
+pub fn find_exception<'a, I>(lines: I) -> Option<(String, Vec<String>)>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut lines = lines
+        .into_iter()
+        .skip_while(|line| !is_exception_line(line));
+    let exception = strip_log_tag(lines.next()?).to_string();
+    let trace = lines
+        .take_while(|line| is_trace_line(line))
+        .take(MAX_TRACE_LINES)
+        .map(|line| strip_log_tag(line).to_string())
+        .collect();
+    Some((exception, trace))
+}
+
+/// Checks a finished session: a non-zero exit code or a fresh file in `crashes/` counts as a crash
+pub fn detect(session: &Session) -> Option<CrashReport> {
+    let crash_file = new_crash_file(&session.data_dir, session.started_at);
+    let failed = session.exit_code.is_some_and(|code| code != 0);
+    if !failed && crash_file.is_none() {
+        return None;
+    }
+
+    // the crash file has the full report, the console only what was printed
+    let crash_text = crash_file
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok());
+    let found = match &crash_text {
+        Some(text) => find_exception(text.lines()),
+        None => None,
+    }
+    .or_else(|| find_exception(session.lines.iter().map(|line| line.text.as_str())));
+    let (exception, stack_trace) = match found {
+        Some((exception, trace)) => (Some(exception), trace),
+        None => (None, Vec::new()),
+    };
+
+    Some(CrashReport {
+        tag_name: session.tag_name.clone(),
+        exit_code: session.exit_code,
+        exception,
+        stack_trace,
+        crash_file,
+        log_path: session.log_path.clone(),
+        profile: toml::to_string_pretty(&session.profile).unwrap_or_default(),
+        java: match &session.java {
+            Some(java) => format!(
+                "{} {} (major {})\n{}\n",
+                java.vendor,
+                java.version,
+                java.major,
+                java.path.display()
+            ),
+            None => "java from PATH\n".to_string(),
+        },
+    })
+}
+
+/// Zips everything a tester needs into `crash-reports/<tag>-<time>.zip`
+pub fn bundle(report: &CrashReport) -> Result<PathBuf, Error> {
+    fs::create_dir_all(reports_dir())?;
+    let path = reports_dir().join(format!(
+        "{}-{}.zip",
+        download::sanitize_file_name(&report.tag_name),
+        chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+    ));
+    let mut zip = zip::ZipWriter::new(File::create(&path)?);
+    let options = FileOptions::default();
+
+    zip.start_file("release.txt", options)?;
+    writeln!(zip, "tag: {}", report.tag_name)?;
+    match report.exit_code {
+        Some(code) => writeln!(zip, "exit code: {}", code)?,
+        None => writeln!(zip, "exit code: none (killed)")?,
+    }
+    if let Some(exception) = &report.exception {
+        writeln!(zip, "exception: {}", exception)?;
+    }
+
+    zip.start_file("java.txt", options)?;
+    zip.write_all(report.java.as_bytes())?;
+
+    zip.start_file("profile.toml", options)?;
+    zip.write_all(report.profile.as_bytes())?;
+
+    zip.start_file("session.log", options)?;
+    zip.write_all(&fs::read(&report.log_path)?)?;
+
+    if let Some(crash_file) = &report.crash_file {
+        let name = crash_file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "bad crash file name"))?;
+        zip.start_file(format!("crashes/{}", name), options)?;
+        zip.write_all(&fs::read(crash_file)?)?;
+    }
+
+    zip.finish()?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::process::LogLine;
+    use crate::ml_core::test_support::temp_dir;
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+
+    fn session(data_dir: &Path, exit_code: Option<i32>, lines: &[&str]) -> Session {
+        Session {
+            tag_name: "v126".to_string(),
+            jar: data_dir.join("v126.jar"),
+            pid: 1,
+            profile: Default::default(),
+            java: None,
+            data_dir: data_dir.to_path_buf(),
+            started: Instant::now(),
+            started_at: SystemTime::now() - Duration::from_secs(60),
+            finished: Some(Instant::now()),
+            exit_code,
+            lines: lines
+                .iter()
+                .map(|text| LogLine {
+                    text: text.to_string(),
+                    stderr: false,
+                })
+                .collect::<VecDeque<_>>(),
+            log_path: data_dir.join("session.log"),
+        }
+    }
+
+    #[test]
+    fn finds_tagged_exceptions_with_causes() {
+        let log = [
+            "[I] Loaded 12 mods",
+            "[E] java.lang.RuntimeException: failed to load map",
+            "[E]     at mindustry.io.SaveIO.load(SaveIO.java:170)",
+            "[E]     at mindustry.core.World.loadMap(World.java:312)",
+            "[E] Caused by: java.io.EOFException",
+            "[E]     at java.io.DataInputStream.readInt(DataInputStream.java:397)",
+            "[E]     ... 12 more",
+            "[I] Saving settings",
+        ];
+        let (exception, trace) = find_exception(log.iter().copied()).unwrap();
+        assert_eq!(exception, "java.lang.RuntimeException: failed to load map");
+        assert_eq!(
+            trace,
+            vec![
+                "at mindustry.io.SaveIO.load(SaveIO.java:170)",
+                "at mindustry.core.World.loadMap(World.java:312)",
+                "Caused by: java.io.EOFException",
+                "at java.io.DataInputStream.readInt(DataInputStream.java:397)",
+                "... 12 more",
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_untagged_exceptions_and_ignores_plain_text() {
+        let log = [
+            "Exception in thread \"main\" java.lang.OutOfMemoryError: Java heap space",
+            "\tat arc.graphics.Pixmap.<init>(Pixmap.java:54)",
+        ];
+        let (exception, trace) = find_exception(log.iter().copied()).unwrap();
+        assert!(exception.starts_with("Exception in thread \"main\""));
+        assert_eq!(trace, vec!["at arc.graphics.Pixmap.<init>(Pixmap.java:54)"]);
+
+        let log = ["[I] No Error happened here", "[W] Some thing: failed"];
+        assert!(find_exception(log.iter().copied()).is_none());
+    }
+
+    #[test]
+    fn detects_crashes_by_exit_code_or_crash_file() {
+        let dir = temp_dir("crash-detect");
+        assert!(detect(&session(&dir, Some(0), &[])).is_none());
+
+        let report = detect(&session(
+            &dir,
+            Some(1),
+            &[
+                "[E] java.lang.IllegalStateException: boom",
+                "    at a.B.c(B.java:1)",
+            ],
+        ))
+        .unwrap();
+        assert_eq!(report.exit_code, Some(1));
+        assert_eq!(
+            report.exception.as_deref(),
+            Some("java.lang.IllegalStateException: boom")
+        );
+        assert_eq!(report.stack_trace, vec!["at a.B.c(B.java:1)"]);
+        assert!(report.crash_file.is_none());
+
+        // a clean exit still counts when the game wrote a crash file, which wins over the console
+        fs::create_dir_all(dir.join("crashes")).unwrap();
+        let crash = dir.join("crashes").join("crash-report.txt");
+        fs::write(
+            &crash,
+            "Mindustry crashed\njava.lang.NullPointerException\n\tat x.Y.z(Y.java:2)\n",
+        )
+        .unwrap();
+        let report = detect(&session(
+            &dir,
+            Some(0),
+            &["[E] java.lang.IllegalStateException: boom"],
+        ))
+        .unwrap();
+        assert_eq!(report.crash_file.as_deref(), Some(crash.as_path()));
+        assert_eq!(
+            report.exception.as_deref(),
+            Some("java.lang.NullPointerException")
+        );
+        assert_eq!(report.stack_trace, vec!["at x.Y.z(Y.java:2)"]);
+
+        // crash files from before the session are old news
+        let mut later = session(&dir, Some(0), &[]);
+        later.started_at = SystemTime::now() + Duration::from_secs(60);
+        assert!(detect(&later).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,399 @@
+use super::http_tools;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How far a running download is
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    pub received: u64,
+    /// From `Content-Length`, or the asset size when the server does not send one
+    pub total: Option<u64>,
+    /// Bytes that were already on disk when a resumed download started
+    pub resumed_from: u64,
+    pub started: Instant,
+}
+
+impl Progress {
+    pub fn new(total: Option<u64>) -> Self {
+        Progress::resumed(0, total)
+    }
+
+    pub fn resumed(offset: u64, total: Option<u64>) -> Self {
+        Progress {
+            received: offset,
+            total: total.filter(|total| *total > 0),
+            resumed_from: offset,
+            started: Instant::now(),
+        }
+    }
+
+    /// Between 0 and 1, `None` when the length is unknown
+    pub fn fraction(&self) -> Option<f32> {
+        self.total
+            .map(|total| (self.received as f64 / total as f64).min(1.0) as f32)
+    }
+
+    /// Average bytes per second since the start, resumed bytes do not count
+    pub fn speed(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            (self.received - self.resumed_from) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        let speed = self.speed();
+        let left = self.total?.saturating_sub(self.received);
+        if speed > 0.0 {
+            Some(Duration::from_secs_f64(left as f64 / speed))
+        } else {
+            None
+        }
+    }
+}
+
+/// Makes a tag or release name usable as a file name on every platform:
+/// separators, `:` and the other characters Windows refuses become `_`
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // no hidden files and no `..`, Windows also drops trailing dots and spaces
+    match sanitized.trim_matches(|c| c == '.' || c == ' ') {
+        "" => "unnamed".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// The partial file next to `target`, e.g. `v126.2.jar.part`
+pub fn part_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+/// Validators of a partial download, saved next to the `.part` file so a later attempt can resume it
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct PartInfo {
+    etag: Option<String>,
+    total: Option<u64>,
+}
+
+fn info_path(part: &Path) -> PathBuf {
+    let mut name = part.file_name().unwrap_or_default().to_os_string();
+    name.push(".json");
+    part.with_file_name(name)
+}
+
+/// Removes the `.part` file of `target` and what was saved to resume it
+pub fn remove_partial(target: &Path) -> Result<(), Error> {
+    let part = part_path(target);
+    for path in [info_path(&part), part].iter() {
+        match fs::remove_file(path) {
+            Err(ref e) if e.kind() == ErrorKind::NotFound => (),
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+/// Downloads `url` to `target` through a `.part` file that is synced and renamed into place
+/// only when the download is complete, so `target` is never a partial file.
+/// An interrupted transfer keeps the `.part` file and the next call resumes it.
+/// `expected_size` is checked when the source published one, then `verify` gets the complete `.part` file,
+/// a failed check removes it. `on_progress` gets every received chunk
+/// and stops the transfer with an `Interrupted` error by returning false, the `.part` file stays.
+pub fn download_file(
+    url: &str,
+    target: &Path,
+    expected_size: Option<u64>,
+    verify: &dyn Fn(&Path) -> Result<(), Error>,
+    on_progress: &mut dyn FnMut(&Progress) -> bool,
+) -> Result<u64, Error> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let part = part_path(target);
+    let size = write_part(url, &part, expected_size, on_progress)?;
+    if let Some(expected) = expected_size.filter(|expected| *expected > 0) {
+        if size != expected {
+            // resuming would not fix it, start over next time
+            remove_partial(target)?;
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("corrupted download: {} bytes, expected {}", size, expected),
+            ));
+        }
+    }
+    if let Err(e) = verify(&part) {
+        println!("{} failed verification: {}", url, e);
+        remove_partial(target)?;
+        return Err(e);
+    }
+    fs::rename(&part, target)?;
+    let _ = fs::remove_file(info_path(&part));
+    Ok(size)
+}
+
+/// `bytes 100-199/1000` of a `Content-Range` header as (first byte, total)
+fn content_range(header: &str) -> Option<(u64, Option<u64>)> {
+    let range = header.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_at(range.find('/')?);
+    let first = span.split('-').next()?.trim().parse().ok()?;
+    Some((first, total[1..].trim().parse().ok()))
+}
+
+/// Asks for the rest of a partial file. `None` when the server sent something else than the
+/// same file from `offset`, a `416` for a range past the end included, the download then starts over.
+fn resume(
+    url: &str,
+    offset: u64,
+    saved: &PartInfo,
+    expected_size: Option<u64>,
+) -> Result<Option<(ureq::Response, PartInfo)>, Error> {
+    let range = format!("bytes={}-", offset);
+    let response = http_tools::send(url, &[("Range", range.as_str())])?;
+    if response.status() != 206 {
+        println!(
+            "{} answered the range request with {}, restarting",
+            url,
+            response.status()
+        );
+        return Ok(None);
+    }
+    let (first, total) = match response.header("content-range").and_then(content_range) {
+        Some(range) => range,
+        None => return Ok(None),
+    };
+    let etag = response.header("etag").map(|etag| etag.to_string());
+    let same_file = match (&saved.etag, &etag) {
+        (Some(saved_etag), Some(etag)) => saved_etag == etag,
+        // without ETags the size has to match what was announced before
+        _ => {
+            let known_total = saved.total.or(expected_size);
+            known_total.is_some() && total == known_total
+        }
+    };
+    if first != offset || !same_file {
+        println!("{} changed since the partial download, restarting", url);
+        return Ok(None);
+    }
+    Ok(Some((response, PartInfo { etag, total })))
+}
+
+fn write_part(
+    url: &str,
+    part: &Path,
+    expected_size: Option<u64>,
+    on_progress: &mut dyn FnMut(&Progress) -> bool,
+) -> Result<u64, Error> {
+    let info = info_path(part);
+    let saved: Option<PartInfo> = fs::read_to_string(&info)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok());
+    let offset = fs::metadata(part).map_or(0, |metadata| metadata.len());
+
+    // local files are cheap to copy again, only HTTP downloads resume
+    let resumed = match saved {
+        Some(saved) if offset > 0 && !url.starts_with("file://") => {
+            match saved.total {
+                // stopped between the last byte and the rename, the checks of the caller decide
+                Some(total) if offset == total => return Ok(offset),
+                Some(total) if offset > total => None,
+                _ => resume(url, offset, &saved, expected_size)?,
+            }
+        }
+        _ => None,
+    };
+    let (mut reader, mut progress, mut file): (Box<dyn Read>, Progress, File) = match resumed {
+        Some((response, part_info)) => {
+            println!("resuming {} at {} bytes", url, offset);
+            let progress = Progress::resumed(offset, part_info.total);
+            let file = OpenOptions::new().append(true).open(part)?;
+            (Box::new(response.into_reader()), progress, file)
+        }
+        None => {
+            let (reader, length, etag) = http_tools::open_download(url)?;
+            let part_info = PartInfo {
+                etag,
+                total: length.or(expected_size),
+            };
+            fs::write(&info, serde_json::to_string(&part_info)?)?;
+            (reader, Progress::new(part_info.total), File::create(part)?)
+        }
+    };
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        if !on_progress(&progress) {
+            file.sync_all()?;
+            return Err(Error::new(ErrorKind::Interrupted, "download stopped"));
+        }
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        progress.received += read as u64;
+    }
+    file.sync_all()?;
+    // a dropped connection can look like a normal end of the body
+    if let Some(total) = progress.total.filter(|total| progress.received < *total) {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "connection closed after {} of {} bytes, the next attempt resumes",
+                progress.received, total
+            ),
+        ));
+    }
+    Ok(progress.received)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::{temp_dir, Response, TestServer};
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Serves `body` with `etag`, the first full response is cut after `cut` bytes
+    fn flaky_server(body: Vec<u8>, etag: &'static str, cut: usize) -> TestServer {
+        let first = std::sync::atomic::AtomicBool::new(true);
+        TestServer::start(move |request| match request.header("range") {
+            Some(range) => {
+                let start: usize = range["bytes=".len()..]
+                    .trim_end_matches('-')
+                    .parse()
+                    .unwrap();
+                Response::new(206, &body[start..])
+                    .header(
+                        "Content-Range",
+                        &format!("bytes {}-{}/{}", start, body.len() - 1, body.len()),
+                    )
+                    .header("ETag", etag)
+            }
+            None if first.swap(false, std::sync::atomic::Ordering::SeqCst) => {
+                Response::ok(body.clone()).header("ETag", etag).cut(cut)
+            }
+            None => Response::ok(body.clone()).header("ETag", etag),
+        })
+    }
+
+    fn download(url: &str, target: &Path, expected_size: Option<u64>) -> Result<u64, Error> {
+        download_file(url, target, expected_size, &|_| Ok(()), &mut |_| true)
+    }
+
+    #[test]
+    fn parses_content_range() {
+        assert_eq!(content_range("bytes 100-199/1000"), Some((100, Some(1000))));
+        assert_eq!(content_range("bytes 0-0/*"), Some((0, None)));
+        assert_eq!(content_range("items 0-1/2"), None);
+        assert_eq!(content_range("bytes 100-199"), None);
+    }
+
+    #[test]
+    fn resumes_a_cut_connection() {
+        let body = data(300_000);
+        let server = flaky_server(body.clone(), "\"a\"", 100_000);
+        let target = temp_dir("resume").join("game.jar");
+
+        let error = download(&server.url("/game.jar"), &target, Some(300_000)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert!(!target.exists());
+        assert_eq!(fs::metadata(part_path(&target)).unwrap().len(), 100_000);
+
+        assert_eq!(
+            download(&server.url("/game.jar"), &target, Some(300_000)).unwrap(),
+            300_000
+        );
+        assert_eq!(fs::read(&target).unwrap(), body);
+        assert!(!part_path(&target).exists());
+        let ranges: Vec<Option<String>> = server
+            .requests()
+            .iter()
+            .map(|request| request.header("range").map(str::to_string))
+            .collect();
+        assert_eq!(ranges, vec![None, Some("bytes=100000-".to_string())]);
+    }
+
+    #[test]
+    fn restarts_when_the_etag_changed() {
+        let body = data(50_000);
+        let target = temp_dir("etag").join("game.jar");
+        let part = part_path(&target);
+        fs::write(&part, &data(10_000)[..]).unwrap();
+        let saved = PartInfo {
+            etag: Some("\"old\"".to_string()),
+            total: Some(50_000),
+        };
+        fs::write(info_path(&part), serde_json::to_string(&saved).unwrap()).unwrap();
+        let server = flaky_server(body.clone(), "\"new\"", usize::MAX);
+
+        assert_eq!(
+            download(&server.url("/game.jar"), &target, None).unwrap(),
+            50_000
+        );
+        assert_eq!(fs::read(&target).unwrap(), body);
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].header("range").is_some());
+        assert!(requests[1].header("range").is_none());
+    }
+
+    #[test]
+    fn restarts_when_the_range_is_refused() {
+        let body = data(20_000);
+        let target = temp_dir("refused").join("game.jar");
+        let part = part_path(&target);
+        fs::write(&part, &body[..]).unwrap();
+        let saved = PartInfo {
+            etag: Some("\"a\"".to_string()),
+            total: None,
+        };
+        fs::write(info_path(&part), serde_json::to_string(&saved).unwrap()).unwrap();
+        let served = body.clone();
+        let server = TestServer::start(move |request| match request.header("range") {
+            Some(_) => Response::new(416, ""),
+            None => Response::ok(served.clone()),
+        });
+
+        assert_eq!(
+            download(&server.url("/game.jar"), &target, None).unwrap(),
+            20_000
+        );
+        assert_eq!(fs::read(&target).unwrap(), body);
+    }
+
+    #[test]
+    fn finishes_a_complete_part_without_a_request() {
+        let body = data(20_000);
+        let target = temp_dir("complete").join("game.jar");
+        let part = part_path(&target);
+        fs::write(&part, &body[..]).unwrap();
+        let saved = PartInfo {
+            etag: None,
+            total: Some(20_000),
+        };
+        fs::write(info_path(&part), serde_json::to_string(&saved).unwrap()).unwrap();
+        let server = TestServer::start(|_| Response::new(416, ""));
+
+        assert_eq!(
+            download(&server.url("/game.jar"), &target, Some(20_000)).unwrap(),
+            20_000
+        );
+        assert_eq!(fs::read(&target).unwrap(), body);
+        assert!(server.requests().is_empty());
+    }
+}
@@ -0,0 +1,549 @@
+use super::download::{self, Progress};
+use super::integrity::{self, Checksum};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemStatus {
+    Queued,
+    Running,
+    /// Stopped with its `.part` file kept, resuming continues from there
+    Paused,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+/// What a running transfer was asked to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Request {
+    Continue,
+    Pause,
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadItem {
+    pub id: u64,
+    /// Shown in the panel, the tag of the version
+    pub label: String,
+    pub url: String,
+    pub target: PathBuf,
+    pub expected_size: Option<u64>,
+    /// Fetched when the download starts, a checksum file may have to be downloaded first
+    pub checksum: Option<Checksum>,
+    pub status: ItemStatus,
+    pub progress: Option<Progress>,
+    request: Request,
+}
+
+/// Called with the finished file, on the download thread
+pub type OnDone = Box<dyn FnOnce(&Path) + Send>;
+
+struct Queue {
+    items: Vec<DownloadItem>,
+    on_done: HashMap<u64, OnDone>,
+    max_running: usize,
+    next_id: u64,
+}
+
+/// Downloads run on their own threads, at most `max_running` at once, the rest wait in order
+#[derive(Clone)]
+pub struct DownloadQueue {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl DownloadQueue {
+    pub fn new(max_running: usize) -> Self {
+        DownloadQueue {
+            queue: Arc::new(Mutex::new(Queue {
+                items: Vec::new(),
+                on_done: HashMap::new(),
+                max_running: max_running.max(1),
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Queues a jar download, `None` when the same file is already waiting or running.
+    /// The jar is verified before it is moved to `target` and `on_done` runs.
+    pub fn push(
+        &self,
+        label: &str,
+        url: &str,
+        target: PathBuf,
+        expected_size: Option<u64>,
+        checksum: Option<Checksum>,
+        on_done: OnDone,
+    ) -> Option<u64> {
+        let id = {
+            let mut queue = self.queue.lock();
+            let busy = queue.items.iter().any(|item| {
+                item.target == target
+                    && (item.status == ItemStatus::Queued || item.status == ItemStatus::Running)
+            });
+            if busy {
+                return None;
+            }
+            // a retried or repeated download replaces its old entry
+            let replaced: Vec<u64> = queue
+                .items
+                .iter()
+                .filter(|item| item.target == target)
+                .map(|item| item.id)
+                .collect();
+            queue.items.retain(|item| item.target != target);
+            queue.on_done.retain(|id, _| !replaced.contains(id));
+            let id = queue.next_id;
+            queue.next_id += 1;
+            queue.items.push(DownloadItem {
+                id,
+                label: label.to_string(),
+                url: url.to_string(),
+                target,
+                expected_size,
+                checksum,
+                status: ItemStatus::Queued,
+                progress: None,
+                request: Request::Continue,
+            });
+            queue.on_done.insert(id, on_done);
+            id
+        };
+        self.schedule();
+        Some(id)
+    }
+
+    /// A copy of every item for drawing
+    pub fn items(&self) -> Vec<DownloadItem> {
+        self.queue.lock().items.clone()
+    }
+
+    pub fn pause(&self, id: u64) {
+        let mut queue = self.queue.lock();
+        if let Some(item) = queue.items.iter_mut().find(|item| item.id == id) {
+            match item.status {
+                ItemStatus::Running => item.request = Request::Pause,
+                ItemStatus::Queued => item.status = ItemStatus::Paused,
+                _ => (),
+            }
+        }
+    }
+
+    /// Puts a paused or failed item back in the queue, it continues from its `.part` file
+    pub fn resume(&self, id: u64) {
+        {
+            let mut queue = self.queue.lock();
+            if let Some(item) = queue.items.iter_mut().find(|item| item.id == id) {
+                if let ItemStatus::Paused | ItemStatus::Failed(_) = item.status {
+                    item.status = ItemStatus::Queued;
+                }
+            }
+        }
+        self.schedule();
+    }
+
+    /// Stops the item and removes its partial file
+    pub fn cancel(&self, id: u64) {
+        let mut queue = self.queue.lock();
+        let item = match queue.items.iter_mut().find(|item| item.id == id) {
+            Some(item) => item,
+            None => return,
+        };
+        match item.status {
+            // the download thread removes the file once the transfer stopped
+            ItemStatus::Running => item.request = Request::Cancel,
+            ItemStatus::Queued | ItemStatus::Paused | ItemStatus::Failed(_) => {
+                item.status = ItemStatus::Cancelled;
+                if let Err(e) = download::remove_partial(&item.target) {
+                    println!("cannot remove partial {}: {}", item.target.display(), e);
+                }
+                queue.on_done.remove(&id);
+            }
+            _ => (),
+        }
+    }
+
+    /// Drops finished, failed and cancelled items from the list
+    pub fn clear_finished(&self) {
+        let mut queue = self.queue.lock();
+        queue.items.retain(|item| {
+            matches!(
+                item.status,
+                ItemStatus::Queued | ItemStatus::Running | ItemStatus::Paused
+            )
+        });
+        let ids: Vec<u64> = queue.items.iter().map(|item| item.id).collect();
+        queue.on_done.retain(|id, _| ids.contains(id));
+    }
+
+    /// Starts queued items while there are free slots
+    fn schedule(&self) {
+        let mut queue = self.queue.lock();
+        let max_running = queue.max_running;
+        loop {
+            let running = queue
+                .items
+                .iter()
+                .filter(|item| item.status == ItemStatus::Running)
+                .count();
+            if running >= max_running {
+                return;
+            }
+            let item = match queue
+                .items
+                .iter_mut()
+                .find(|item| item.status == ItemStatus::Queued)
+            {
+                Some(item) => item,
+                None => return,
+            };
+            item.status = ItemStatus::Running;
+            item.request = Request::Continue;
+            let item = item.clone();
+            let this = self.clone();
+            thread::spawn(move || this.run(item));
+        }
+    }
+
+    fn run(&self, item: DownloadItem) {
+        println!("Starting download of {}", item.label);
+        let sha256 = item.checksum.as_ref().map_or(Ok(None), Checksum::resolve);
+        let result = sha256.and_then(|sha256| {
+            download::download_file(
+                &item.url,
+                &item.target,
+                item.expected_size,
+                &|part| integrity::verify(part, sha256.as_deref()),
+                &mut |progress| {
+                    let mut queue = self.queue.lock();
+                    match queue.items.iter_mut().find(|queued| queued.id == item.id) {
+                        Some(queued) => {
+                            queued.progress = Some(*progress);
+                            queued.request == Request::Continue
+                        }
+                        None => false,
+                    }
+                },
+            )
+        });
+
+        let on_done = {
+            let mut queue = self.queue.lock();
+            let request = queue
+                .items
+                .iter()
+                .find(|queued| queued.id == item.id)
+                .map_or(Request::Cancel, |queued| queued.request);
+            let status = match &result {
+                Ok(_) => ItemStatus::Done,
+                Err(e) if e.kind() == ErrorKind::Interrupted && request == Request::Pause => {
+                    ItemStatus::Paused
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted && request == Request::Cancel => {
+                    if let Err(e) = download::remove_partial(&item.target) {
+                        println!("cannot remove partial {}: {}", item.target.display(), e);
+                    }
+                    ItemStatus::Cancelled
+                }
+                // an `Interrupted` read error nobody asked for included, the part stays for a retry
+                Err(e) => ItemStatus::Failed(e.to_string()),
+            };
+            println!("Download of {}: {:?}", item.label, status);
+            let done = status == ItemStatus::Done;
+            if let Some(queued) = queue.items.iter_mut().find(|queued| queued.id == item.id) {
+                queued.status = status;
+            }
+            if done || request == Request::Cancel {
+                queue.on_done.remove(&item.id)
+            } else {
+                None
+            }
+        };
+        if let (Ok(_), Some(on_done)) = (&result, on_done) {
+            on_done(&item.target);
+        }
+        self.schedule();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::{temp_dir, Response, TestServer};
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Condvar, Mutex as StdMutex};
+    use std::time::{Duration, Instant};
+
+    /// Holds every response back until the test opens it
+    #[derive(Default)]
+    struct Gate {
+        open: StdMutex<bool>,
+        changed: Condvar,
+    }
+
+    impl Gate {
+        fn wait(&self) {
+            let mut open = self.open.lock().unwrap();
+            while !*open {
+                open = self.changed.wait(open).unwrap();
+            }
+        }
+
+        fn open(&self) {
+            *self.open.lock().unwrap() = true;
+            self.changed.notify_all();
+        }
+    }
+
+    fn jar() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("META-INF/MANIFEST.MF", Default::default())
+            .unwrap();
+        zip.write_all(b"Manifest-Version: 1.0\nMain-Class: mindustry.Main\n")
+            .unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// Serves a valid jar at every path once the gate is open
+    fn jar_server(gate: Arc<Gate>) -> TestServer {
+        let jar = jar();
+        TestServer::start(move |_| {
+            gate.wait();
+            Response::ok(jar.clone())
+        })
+    }
+
+    fn counting(count: &Arc<AtomicUsize>) -> OnDone {
+        let count = count.clone();
+        Box::new(move |_| {
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+    }
+
+    fn status(queue: &DownloadQueue, id: u64) -> ItemStatus {
+        queue
+            .items()
+            .into_iter()
+            .find(|item| item.id == id)
+            .unwrap()
+            .status
+    }
+
+    /// Waits for a status, any `Failed` matches `Failed` with an empty message
+    fn wait_for(queue: &DownloadQueue, id: u64, wanted: ItemStatus) {
+        let start = Instant::now();
+        while std::mem::discriminant(&status(queue, id)) != std::mem::discriminant(&wanted) {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "{:?} instead of {:?}",
+                status(queue, id),
+                wanted
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn runs_at_most_max_running() {
+        let gate = Arc::new(Gate::default());
+        let server = jar_server(gate.clone());
+        let dir = temp_dir("queue-limit");
+        let queue = DownloadQueue::new(1);
+        let done = Arc::new(AtomicUsize::new(0));
+        let first = queue
+            .push(
+                "a",
+                &server.url("/a.jar"),
+                dir.join("a.jar"),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+        let second = queue
+            .push(
+                "b",
+                &server.url("/b.jar"),
+                dir.join("b.jar"),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+        assert_eq!(status(&queue, first), ItemStatus::Running);
+        assert_eq!(status(&queue, second), ItemStatus::Queued);
+
+        gate.open();
+        wait_for(&queue, first, ItemStatus::Done);
+        wait_for(&queue, second, ItemStatus::Done);
+        assert_eq!(done.load(Ordering::SeqCst), 2);
+        assert_eq!(fs::read(dir.join("b.jar")).unwrap(), jar());
+    }
+
+    #[test]
+    fn pauses_and_resumes() {
+        let gate = Arc::new(Gate::default());
+        let server = jar_server(gate.clone());
+        let dir = temp_dir("queue-pause");
+        let target = dir.join("a.jar");
+        let queue = DownloadQueue::new(1);
+        let done = Arc::new(AtomicUsize::new(0));
+        let id = queue
+            .push(
+                "a",
+                &server.url("/a.jar"),
+                target.clone(),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+        queue.pause(id);
+        gate.open();
+        wait_for(&queue, id, ItemStatus::Paused);
+        assert!(download::part_path(&target).exists());
+        assert!(!target.exists());
+        assert_eq!(done.load(Ordering::SeqCst), 0);
+
+        queue.resume(id);
+        wait_for(&queue, id, ItemStatus::Done);
+        assert_eq!(fs::read(&target).unwrap(), jar());
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn cancelling_removes_the_part_file() {
+        let gate = Arc::new(Gate::default());
+        let server = jar_server(gate.clone());
+        let dir = temp_dir("queue-cancel");
+        let (running, waiting) = (dir.join("a.jar"), dir.join("b.jar"));
+        // a part left by an earlier attempt
+        File::create(download::part_path(&waiting)).unwrap();
+        let queue = DownloadQueue::new(1);
+        let done = Arc::new(AtomicUsize::new(0));
+        let first = queue
+            .push(
+                "a",
+                &server.url("/a.jar"),
+                running.clone(),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+        let second = queue
+            .push(
+                "b",
+                &server.url("/b.jar"),
+                waiting.clone(),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+
+        queue.cancel(second);
+        assert_eq!(status(&queue, second), ItemStatus::Cancelled);
+        assert!(!download::part_path(&waiting).exists());
+
+        queue.cancel(first);
+        gate.open();
+        wait_for(&queue, first, ItemStatus::Cancelled);
+        assert!(!download::part_path(&running).exists());
+        assert!(!running.exists());
+        assert_eq!(done.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn retries_a_failed_download() {
+        let data = jar();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let server = TestServer::start(move |_| {
+            // the first attempt of each item fails
+            if counter.fetch_add(1, Ordering::SeqCst).is_multiple_of(2) {
+                Response::new(500, "busy")
+            } else {
+                Response::ok(data.clone())
+            }
+        });
+        let dir = temp_dir("queue-retry");
+        let target = dir.join("a.jar");
+        let queue = DownloadQueue::new(1);
+        let done = Arc::new(AtomicUsize::new(0));
+        let id = queue
+            .push(
+                "a",
+                &server.url("/a.jar"),
+                target.clone(),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+        wait_for(&queue, id, ItemStatus::Failed(String::new()));
+        assert!(!target.exists());
+
+        queue.resume(id);
+        wait_for(&queue, id, ItemStatus::Done);
+        assert_eq!(fs::read(&target).unwrap(), jar());
+
+        // installing again pushes a new item in place of the failed one
+        let other = dir.join("b.jar");
+        let failed = queue
+            .push(
+                "b",
+                &server.url("/b.jar"),
+                other.clone(),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+        wait_for(&queue, failed, ItemStatus::Failed(String::new()));
+        let retried = queue
+            .push(
+                "b",
+                &server.url("/b.jar"),
+                other.clone(),
+                None,
+                None,
+                counting(&done),
+            )
+            .unwrap();
+        wait_for(&queue, retried, ItemStatus::Done);
+        assert!(queue.items().iter().all(|item| item.id != failed));
+        assert_eq!(done.load(Ordering::SeqCst), 2);
+        assert_eq!(requests.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn refuses_a_target_already_queued() {
+        let gate = Arc::new(Gate::default());
+        let server = jar_server(gate.clone());
+        let dir = temp_dir("queue-busy");
+        let queue = DownloadQueue::new(1);
+        let done = Arc::new(AtomicUsize::new(0));
+        let url = server.url("/a.jar");
+        let push =
+            |target: &str| queue.push("a", &url, dir.join(target), None, None, counting(&done));
+        let running = push("a.jar").unwrap();
+        assert_eq!(push("a.jar"), None);
+        let waiting = push("b.jar").unwrap();
+        assert_eq!(push("b.jar"), None);
+        assert_eq!(queue.items().len(), 2);
+
+        gate.open();
+        wait_for(&queue, running, ItemStatus::Done);
+        wait_for(&queue, waiting, ItemStatus::Done);
+        // a finished download can be installed again
+        assert!(push("a.jar").is_some());
+    }
+}
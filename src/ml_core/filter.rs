@@ -0,0 +1,278 @@
+use super::releases;
+use crate::models::profile::Channel;
+use crate::models::version::Release;
+
+/// Order of the versions list, newest or biggest first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Version,
+    Published,
+    Size,
+}
+
+impl SortOrder {
+    /// The order after this one, the sort button cycles through them
+    pub fn next(self) -> SortOrder {
+        match self {
+            SortOrder::Version => SortOrder::Published,
+            SortOrder::Published => SortOrder::Size,
+            SortOrder::Size => SortOrder::Version,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Version => "Version",
+            SortOrder::Published => "Date",
+            SortOrder::Size => "Size",
+        }
+    }
+}
+
+/// What the versions list shows
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReleaseFilter {
+    pub query: String,
+    pub stable: bool,
+    pub bleeding_edge: bool,
+    pub installed_only: bool,
+    /// Show pre-releases, they are hidden when off
+    pub prerelease: bool,
+    pub sort: SortOrder,
+}
+
+impl Default for ReleaseFilter {
+    fn default() -> Self {
+        ReleaseFilter {
+            query: String::new(),
+            stable: true,
+            bleeding_edge: true,
+            installed_only: false,
+            prerelease: true,
+            sort: SortOrder::Version,
+        }
+    }
+}
+
+/// True when the characters of `query` appear in `text` in order, ignoring case.
+/// A substring always matches, so does `b126` for `Build 126`.
+pub fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|wanted| text.any(|c| c == wanted))
+}
+
+impl ReleaseFilter {
+    /// Custom sources have no channel toggle and are filtered by the rest only
+    pub fn matches(&self, release: &Release) -> bool {
+        let channel = match release.channel {
+            Channel::Stable => self.stable,
+            Channel::BleedingEdge => self.bleeding_edge,
+            Channel::Custom => true,
+        };
+        channel
+            && (release.installed || !self.installed_only)
+            && (!release.prerelease || self.prerelease)
+            && (fuzzy_match(&self.query, &release.name)
+                || fuzzy_match(&self.query, &release.tag_name))
+    }
+
+    /// Positions of the matching releases in the chosen order
+    pub fn apply(&self, all: &[Release]) -> Vec<usize> {
+        let mut shown: Vec<usize> = (0..all.len())
+            .filter(|&index| self.matches(&all[index]))
+            .collect();
+        match self.sort {
+            SortOrder::Version => {
+                releases::sort_by_version(&mut shown, |&index| &all[index]);
+                shown.reverse();
+            }
+            SortOrder::Published => {
+                shown.sort_by_key(|&index| std::cmp::Reverse(all[index].published_at))
+            }
+            // releases without a desktop jar go last
+            SortOrder::Size => shown.sort_by_key(|&index| {
+                std::cmp::Reverse(all[index].desktop.as_ref().map_or(0, |asset| asset.size))
+            }),
+        }
+        shown
+    }
+}
+
+/// The sorted list and the latest releases, kept between frames. Sorting thousands of builds
+/// every frame is slow, they are sorted again only when the filter or the list changed.
+/// `revision` is bumped by every change of the list.
+#[derive(Default)]
+pub struct SortedView {
+    shown_for: Option<(ReleaseFilter, u64)>,
+    shown: Vec<usize>,
+    latest_for: Option<u64>,
+    latest: Vec<(Channel, usize)>,
+}
+
+impl SortedView {
+    /// The releases `filter.apply` picks for the list at `revision`
+    pub fn shown<'a>(
+        &mut self,
+        filter: &ReleaseFilter,
+        revision: u64,
+        all: &'a [Release],
+    ) -> Vec<&'a Release> {
+        if self.shown_for.as_ref() != Some(&(filter.clone(), revision)) {
+            self.shown = filter.apply(all);
+            self.shown_for = Some((filter.clone(), revision));
+        }
+        self.shown
+            .iter()
+            .filter_map(|&index| all.get(index))
+            .collect()
+    }
+
+    /// What `releases::latest_index` picks for the list at `revision`
+    pub fn latest<'a>(
+        &mut self,
+        channel: Channel,
+        revision: u64,
+        all: &'a [Release],
+    ) -> Option<&'a Release> {
+        if self.latest_for != Some(revision) {
+            self.latest = [Channel::Stable, Channel::BleedingEdge, Channel::Custom]
+                .iter()
+                .filter_map(|&channel| Some((channel, releases::latest_index(all, channel)?)))
+                .collect();
+            self.latest_for = Some(revision);
+        }
+        self.latest
+            .iter()
+            .find(|(latest_channel, _)| *latest_channel == channel)
+            .and_then(|&(_, index)| all.get(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::version::ReleaseAsset;
+
+    fn release(tag_name: &str, channel: Channel) -> Release {
+        Release {
+            name: tag_name.to_string(),
+            tag_name: tag_name.to_string(),
+            version: crate::models::game_version::GameVersion::parse(tag_name),
+            channel,
+            ..Release::default()
+        }
+    }
+
+    #[test]
+    fn matches_fuzzy_queries() {
+        assert!(fuzzy_match("", "anything"));
+        assert!(fuzzy_match("126", "Build 126"));
+        assert!(fuzzy_match("b126", "Build 126"));
+        assert!(fuzzy_match("BUILD 12", "build 126"));
+        assert!(!fuzzy_match("621", "Build 126"));
+        assert!(!fuzzy_match("build 1267", "Build 126"));
+    }
+
+    #[test]
+    fn filters_by_channel_state_and_query() {
+        let mut stable = release("v126", Channel::Stable);
+        stable.name = "Build 126".to_string();
+        let mut prerelease = release("v146", Channel::Stable);
+        prerelease.prerelease = true;
+        let be = release("25872", Channel::BleedingEdge);
+        let custom = release("foo-1", Channel::Custom);
+        let all = [stable, prerelease, be, custom];
+        let tags = |filter: &ReleaseFilter| -> Vec<&str> {
+            all.iter()
+                .filter(|release| filter.matches(release))
+                .map(|release| release.tag_name.as_str())
+                .collect()
+        };
+
+        let mut filter = ReleaseFilter::default();
+        assert_eq!(tags(&filter), vec!["v126", "v146", "25872", "foo-1"]);
+        filter.stable = false;
+        filter.bleeding_edge = false;
+        assert_eq!(tags(&filter), vec!["foo-1"]);
+        filter.stable = true;
+        filter.prerelease = false;
+        assert_eq!(tags(&filter), vec!["v126", "foo-1"]);
+        // the query looks at the name and the tag
+        filter.query = "build".to_string();
+        assert_eq!(tags(&filter), vec!["v126"]);
+        filter.query = "v126".to_string();
+        assert_eq!(tags(&filter), vec!["v126"]);
+        filter.query.clear();
+        filter.installed_only = true;
+        assert!(tags(&filter).is_empty());
+    }
+
+    #[test]
+    fn applies_the_sort_order() {
+        let mut all = vec![
+            release("v126", Channel::Stable),
+            release("v146", Channel::Stable),
+            release("v104", Channel::Stable),
+        ];
+        all[0].published_at = Some(chrono::Utc::now());
+        all[1].published_at = Some(chrono::Utc::now() - chrono::Duration::days(1));
+        all[1].desktop = Some(ReleaseAsset {
+            size: 10,
+            ..ReleaseAsset::default()
+        });
+        all[2].desktop = Some(ReleaseAsset {
+            size: 20,
+            ..ReleaseAsset::default()
+        });
+        let mut filter = ReleaseFilter::default();
+        assert_eq!(filter.apply(&all), vec![1, 0, 2]);
+        filter.sort = SortOrder::Published;
+        assert_eq!(filter.apply(&all), vec![0, 1, 2]);
+        filter.sort = SortOrder::Size;
+        assert_eq!(filter.apply(&all), vec![2, 1, 0]);
+        filter.query = "146".to_string();
+        assert_eq!(filter.apply(&all), vec![1]);
+    }
+
+    #[test]
+    fn sorts_again_only_after_a_change() {
+        let mut all = vec![
+            release("v104.6", Channel::Stable),
+            release("v146", Channel::Stable),
+            release("25872", Channel::BleedingEdge),
+        ];
+        let filter = ReleaseFilter {
+            bleeding_edge: false,
+            ..ReleaseFilter::default()
+        };
+        let mut view = SortedView::default();
+        let tags = |shown: Vec<&Release>| -> Vec<String> {
+            shown
+                .iter()
+                .map(|release| release.tag_name.clone())
+                .collect()
+        };
+        assert_eq!(tags(view.shown(&filter, 1, &all)), vec!["v146", "v104.6"]);
+        assert_eq!(
+            view.latest(Channel::Stable, 1, &all).unwrap().tag_name,
+            "v146"
+        );
+
+        // the same revision keeps the order it already has
+        all[0].version = crate::models::game_version::GameVersion::parse("v200");
+        assert_eq!(tags(view.shown(&filter, 1, &all)), vec!["v146", "v104.6"]);
+        assert_eq!(tags(view.shown(&filter, 2, &all)), vec!["v104.6", "v146"]);
+        assert_eq!(
+            view.latest(Channel::Stable, 2, &all).unwrap().tag_name,
+            "v104.6"
+        );
+
+        let everything = ReleaseFilter::default();
+        assert_eq!(view.shown(&everything, 2, &all).len(), 3);
+        assert_eq!(view.latest(Channel::Custom, 2, &all), None);
+    }
+}
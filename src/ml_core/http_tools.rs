@@ -1,20 +1,133 @@
-use std::io::{Read, Error};
+use chrono::{DateTime, Local, TimeZone};
+use std::fmt;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read};
+use std::sync::RwLock;
 
-pub fn http_request(url: &str) -> Result<String, Error> {
-    let response = ureq::get(url).call();
+/// Personal access token, sent only to GitHub hosts
+static GITHUB_TOKEN: RwLock<Option<String>> = RwLock::new(None);
 
-    //println!("info: status: {}", res.status());
+/// Hosts that get the `Authorization` header, asset downloads start at `github.com`
+const GITHUB_HOSTS: [&str; 3] = ["api.github.com", "github.com", "uploads.github.com"];
 
-    let body = response.into_string();
+pub fn set_github_token(token: Option<String>) {
+    *GITHUB_TOKEN.write().unwrap() = token.filter(|token| !token.is_empty());
+}
+
+/// Error payload of a request refused by the API rate limit
+#[derive(Debug)]
+pub struct RateLimited {
+    pub reset: DateTime<Local>,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rate limited until {}", self.reset.format("%H:%M"))
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// The rate limit behind an error returned by `get` or `load_file`
+pub fn rate_limited(error: &Error) -> Option<&RateLimited> {
+    error.get_ref()?.downcast_ref::<RateLimited>()
+}
+
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', ':', '?']).next().unwrap_or("")
+}
+
+/// A GET request with the token attached when the URL points to GitHub
+pub fn request(url: &str) -> ureq::Request {
+    let mut request = ureq::get(url);
+    if GITHUB_HOSTS.contains(&host(url).to_ascii_lowercase().as_str()) {
+        if let Some(token) = GITHUB_TOKEN.read().unwrap().as_ref() {
+            request.set("Authorization", &format!("Bearer {}", token));
+        }
+    }
+    request
+}
 
-    body
+/// True for errors of requests that never reached the server: no DNS, no route, connection refused
+pub fn is_offline(error: &Error) -> bool {
+    error.kind() == ErrorKind::NotConnected
 }
 
-pub fn load_file(url: &str) -> Box<dyn Read> {
-    let reader = ureq::get(&url)
-        .timeout_connect(5_000)
-        .timeout_read(1_000)
-        .call()
-        .into_reader();
-    Box::new(reader)
-}
\ No newline at end of file
+/// A request that could not connect becomes a `NotConnected` error, HTTP errors stay responses
+fn connected(url: &str, response: ureq::Response) -> Result<ureq::Response, Error> {
+    match response.synthetic_error() {
+        Some(ureq::Error::DnsFailed(e)) | Some(ureq::Error::ConnectionFailed(e)) => {
+            Err(Error::new(
+                ErrorKind::NotConnected,
+                format!("cannot connect to {}: {}", host(url), e),
+            ))
+        }
+        Some(ureq::Error::Io(e)) => Err(Error::new(
+            ErrorKind::NotConnected,
+            format!("{}: {}", host(url), e),
+        )),
+        _ => Ok(response),
+    }
+}
+
+/// Turns an HTTP error into `Err`. An exhausted rate limit becomes a `RateLimited` error.
+fn check(url: &str, response: ureq::Response) -> Result<ureq::Response, Error> {
+    if !response.error() {
+        return Ok(response);
+    }
+    let limited = (response.status() == 403 || response.status() == 429)
+        && response.header("x-ratelimit-remaining") == Some("0");
+    let reset = response
+        .header("x-ratelimit-reset")
+        .and_then(|reset| reset.parse::<i64>().ok());
+    if let (true, Some(reset)) = (limited, reset) {
+        return Err(Error::other(RateLimited {
+            reset: Local.timestamp(reset, 0),
+        }));
+    }
+    Err(Error::other(format!(
+        "{} returned {}",
+        url,
+        response.status_line()
+    )))
+}
+
+/// GET with extra request headers. Only a failed connection is `Err`, the caller checks the status.
+pub fn send(url: &str, headers: &[(&str, &str)]) -> Result<ureq::Response, Error> {
+    let mut request = request(url);
+    for (name, value) in headers {
+        request.set(name, value);
+    }
+    let response = request.timeout_connect(5_000).timeout_read(10_000).call();
+    connected(url, response)
+}
+
+/// GET with extra request headers. HTTP errors are returned as `Err`, the caller reads the headers it needs.
+pub fn get(url: &str, headers: &[(&str, &str)]) -> Result<ureq::Response, Error> {
+    check(url, send(url, headers)?)
+}
+
+/// Opens a download, `file://` URLs of local directory sources are read from disk
+pub fn load_file(url: &str) -> Result<Box<dyn Read>, Error> {
+    Ok(open_download(url)?.0)
+}
+
+/// An opened download with its length and `ETag`
+pub type Download = (Box<dyn Read>, Option<u64>, Option<String>);
+
+/// Like `load_file`, with the length from `Content-Length` and the `ETag` when the server sent them
+pub fn open_download(url: &str) -> Result<Download, Error> {
+    if let Some(path) = url.strip_prefix("file://") {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        return Ok((Box::new(file), Some(length), None));
+    }
+    // the same timeouts as every other request, a slow connection must not fail the download
+    let response = get(url, &[])?;
+    let length = response
+        .header("content-length")
+        .and_then(|length| length.parse().ok());
+    let etag = response.header("etag").map(|etag| etag.to_string());
+    Ok((Box::new(response.into_reader()), length, etag))
+}
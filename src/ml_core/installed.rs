@@ -0,0 +1,456 @@
+use super::launcher;
+use super::paths;
+use crate::models::game_version::GameVersion;
+use crate::models::install::{InstallDatabase, InstallRecord};
+use crate::models::source::ReleaseSource;
+use crate::models::version::Release;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Error, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const DATABASE_FILE: &str = "installs.json";
+
+/// Held for every load-change-save of the database, downloads finish on their own threads
+static DATABASE_LOCK: Mutex<()> = Mutex::new(());
+
+impl InstallDatabase {
+    /// A missing database is an empty one, a broken one is an error so it is not overwritten
+    pub fn load() -> Result<Self, Error> {
+        match fs::read_to_string(paths::data_dir().join(DATABASE_FILE)) {
+            Ok(data) => {
+                serde_json::from_str(&data).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+            }
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(InstallDatabase::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Synced to a temporary file and renamed over the old one, a crash leaves one or the other
+    pub fn save(&self) -> Result<(), Error> {
+        let data = serde_json::to_string_pretty(self)?;
+        let path = paths::data_dir().join(DATABASE_FILE);
+        let temp = path.with_extension("json.tmp");
+        let mut file = File::create(&temp)?;
+        file.write_all(data.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp, &path)
+    }
+
+    pub fn find(&self, path: &Path) -> Option<&InstallRecord> {
+        self.installs.iter().find(|record| record.path == path)
+    }
+}
+
+/// Loads the database, applies `change` and saves it
+fn update(change: impl FnOnce(&mut InstallDatabase)) -> Result<(), Error> {
+    let _guard = DATABASE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut database = InstallDatabase::load()?;
+    change(&mut database);
+    database.save()
+}
+
+/// The database for matching jars, a broken one is reported and matched without
+pub fn database() -> InstallDatabase {
+    InstallDatabase::load().unwrap_or_else(|e| {
+        println!("cannot read {}: {}", DATABASE_FILE, e);
+        InstallDatabase::default()
+    })
+}
+
+/// The record of the desktop jar of `release` at `path`, hashed from the file on disk
+fn new_record(
+    release: &Release,
+    source: Option<&ReleaseSource>,
+    path: &Path,
+) -> Result<InstallRecord, Error> {
+    Ok(InstallRecord {
+        tag_name: release.tag_name.clone(),
+        source: release.source.clone(),
+        repo: source.and_then(|source| {
+            source
+                .repo
+                .clone()
+                .or_else(|| source.api_url.clone())
+                .or_else(|| source.path.clone())
+        }),
+        asset_name: release
+            .desktop
+            .as_ref()
+            .map_or_else(String::new, |asset| asset.name.clone()),
+        path: path.to_path_buf(),
+        size: fs::metadata(path)?.len(),
+        sha256: sha256_file(path)?,
+        installed_at: Utc::now(),
+        last_played: None,
+        play_time: 0,
+    })
+}
+
+/// Records a finished install of the desktop jar of `release` at `path`, replacing an older record of the file
+pub fn record_install(
+    release: &Release,
+    source: Option<&ReleaseSource>,
+    path: &Path,
+) -> Result<(), Error> {
+    let record = new_record(release, source, path)?;
+    update(|database| {
+        database.installs.retain(|old| old.path != record.path);
+        database.installs.push(record);
+    })
+}
+
+/// Drops the record of a deleted jar
+pub fn forget(path: &Path) -> Result<(), Error> {
+    update(|database| database.installs.retain(|record| record.path != path))
+}
+
+/// Sets the last launch of the jar at `path`. A jar the launcher did not install
+/// (copied in by hand, or from before the database) gets its record on the first launch.
+pub fn record_launch(release: &Release, path: &Path) -> Result<(), Error> {
+    let new = match database().find(path) {
+        Some(_) => None,
+        None => Some(new_record(release, None, path)?),
+    };
+    update(|database| {
+        if let Some(record) = new {
+            database.installs.retain(|old| old.path != record.path);
+            database.installs.push(record);
+        }
+        if let Some(record) = database
+            .installs
+            .iter_mut()
+            .find(|record| record.path == path)
+        {
+            record.last_played = Some(Utc::now());
+        }
+    })
+}
+
+pub fn add_play_time(path: &Path, played: Duration) -> Result<(), Error> {
+    update(|database| {
+        if let Some(record) = database
+            .installs
+            .iter_mut()
+            .find(|record| record.path == path)
+        {
+            record.play_time += played.as_secs();
+        }
+    })
+}
+
+/// A jar in the installation directory
+#[derive(Debug, Clone)]
+pub struct LocalJar {
+    pub path: PathBuf,
+    pub size: u64,
+    /// What the jar says it is in its `version.properties`
+    pub version: Option<GameVersion>,
+    /// Computed on the first hash comparison, most jars are matched by name first
+    sha256: Option<String>,
+}
+
+impl LocalJar {
+    fn sha256(&mut self) -> Option<&str> {
+        if self.sha256.is_none() {
+            match sha256_file(&self.path) {
+                Ok(hash) => self.sha256 = Some(hash),
+                Err(e) => println!("cannot hash {}: {}", self.path.display(), e),
+            }
+        }
+        self.sha256.as_deref()
+    }
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Reads the version the game was built as from its `version.properties`
+fn jar_version(path: &Path) -> Option<GameVersion> {
+    let mut zip = zip::ZipArchive::new(File::open(path).ok()?).ok()?;
+    let mut properties = String::new();
+    zip.by_name("version.properties")
+        .ok()?
+        .read_to_string(&mut properties)
+        .ok()?;
+    properties_version(&properties)
+}
+
+/// Releases have `build=126.2` (or `build=146` without a revision),
+/// bleeding-edge jars `type=bleeding-edge` with the build number in `build`.
+fn properties_version(properties: &str) -> Option<GameVersion> {
+    let value = |key: &str| {
+        properties.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            if name.trim() == key {
+                Some(value.trim().to_string())
+            } else {
+                None
+            }
+        })
+    };
+    let build = value("build")?;
+    let mut numbers = build.splitn(2, '.');
+    let major = numbers.next()?.parse().ok()?;
+    if value("type").as_deref() == Some("bleeding-edge") {
+        return Some(GameVersion::Build(major));
+    }
+    let minor = match numbers.next() {
+        Some(minor) => minor.parse().ok()?,
+        None => 0,
+    };
+    Some(GameVersion::Release { major, minor })
+}
+
+/// Lists the jars of the installation directory, a missing directory has none
+pub fn scan(install_dir: &str) -> Vec<LocalJar> {
+    let entries = match fs::read_dir(install_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() || path.extension()? != "jar" {
+                return None;
+            }
+            Some(LocalJar {
+                version: jar_version(&path),
+                path,
+                size: metadata.len(),
+                sha256: None,
+            })
+        })
+        .collect()
+}
+
+fn claim(found: &mut [Option<usize>], taken: &mut [bool], release: usize, jar: Option<usize>) {
+    if let Some(jar) = jar {
+        found[release] = Some(jar);
+        taken[jar] = true;
+    }
+}
+
+/// Sets `installed` and `jar` of every release. A jar belongs to a release when the install
+/// database recorded it for the tag, when it is named after the tag, when its `version.properties`
+/// has the release version, or when its hash matches the published SHA-256 of the desktop asset.
+/// Every jar belongs to one release at most: the strongest match wins, and a version is only
+/// matched when exactly one release and one jar left have it.
+pub fn mark(
+    releases: &mut [Release],
+    install_dir: &str,
+    jars: &mut [LocalJar],
+    database: &InstallDatabase,
+) {
+    let mut found: Vec<Option<usize>> = vec![None; releases.len()];
+    let mut taken = vec![false; jars.len()];
+    for (index, release) in releases.iter().enumerate() {
+        let jar = (0..jars.len()).find(|&jar| {
+            !taken[jar]
+                && database.find(&jars[jar].path).is_some_and(|record| {
+                    record.tag_name == release.tag_name && record.source == release.source
+                })
+        });
+        claim(&mut found, &mut taken, index, jar);
+    }
+    for (index, release) in releases.iter().enumerate() {
+        if found[index].is_none() {
+            let named = launcher::jar_path(install_dir, &release.source, &release.tag_name);
+            let jar = (0..jars.len()).find(|&jar| !taken[jar] && jars[jar].path == named);
+            claim(&mut found, &mut taken, index, jar);
+        }
+    }
+    for (index, release) in releases.iter().enumerate() {
+        if found[index].is_some() || release.version.is_none() {
+            continue;
+        }
+        let same_version = releases
+            .iter()
+            .enumerate()
+            .filter(|(other, other_release)| {
+                found[*other].is_none() && other_release.version == release.version
+            })
+            .count();
+        let mut candidates =
+            (0..jars.len()).filter(|&jar| !taken[jar] && jars[jar].version == release.version);
+        if let (1, Some(jar), None) = (same_version, candidates.next(), candidates.next()) {
+            claim(&mut found, &mut taken, index, Some(jar));
+        }
+    }
+    for (index, release) in releases.iter().enumerate() {
+        if found[index].is_some() {
+            continue;
+        }
+        if let Some((size, expected)) = release
+            .desktop
+            .as_ref()
+            .and_then(|asset| Some((asset.size, asset.sha256.as_deref()?)))
+        {
+            let jar = (0..jars.len()).find(|&jar| {
+                !taken[jar] && jars[jar].size == size && jars[jar].sha256() == Some(expected)
+            });
+            claim(&mut found, &mut taken, index, jar);
+        }
+    }
+
+    for (release, jar) in releases.iter_mut().zip(found) {
+        release.jar = jar.map(|jar| jars[jar].path.clone());
+        release.installed = release.jar.is_some();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::temp_dir;
+    use crate::models::profile::Channel;
+
+    /// `version.properties` as the official builds ship it
+    const STABLE: &str = "#Mon Oct 05 20:21:46 UTC 2020\ntype=official\nnumber=6\nmodifier=release\nbuild=126.2\ncommitHash=5ac85b0\n";
+    const STABLE_NO_REVISION: &str =
+        "type=official\nnumber=7\nmodifier=release\nbuild=146\ncommitHash=7a4e2b5\n";
+    const BLEEDING_EDGE: &str =
+        "type=bleeding-edge\nnumber=7\nmodifier=release\nbuild=25872\ncommitHash=0a5e1c7\n";
+
+    fn release(tag_name: &str, source: &str) -> Release {
+        Release {
+            name: tag_name.to_string(),
+            tag_name: tag_name.to_string(),
+            version: GameVersion::parse(tag_name),
+            published_at: None,
+            assets: Vec::new(),
+            desktop: None,
+            server: None,
+            body: None,
+            html_url: None,
+            prerelease: false,
+            installed: false,
+            jar: None,
+            channel: Channel::Stable,
+            source: source.to_string(),
+        }
+    }
+
+    fn jar(path: PathBuf, version: &str) -> LocalJar {
+        LocalJar {
+            path,
+            size: 1,
+            version: GameVersion::parse(version),
+            sha256: None,
+        }
+    }
+
+    #[test]
+    fn reads_the_version_properties() {
+        assert_eq!(
+            properties_version(STABLE),
+            Some(GameVersion::Release {
+                major: 126,
+                minor: 2
+            })
+        );
+        assert_eq!(
+            properties_version(STABLE_NO_REVISION),
+            Some(GameVersion::Release {
+                major: 146,
+                minor: 0
+            })
+        );
+        assert_eq!(
+            properties_version(BLEEDING_EDGE),
+            Some(GameVersion::Build(25872))
+        );
+        assert_eq!(properties_version("type=official\n"), None);
+    }
+
+    #[test]
+    fn reads_the_version_of_a_jar() {
+        let path = temp_dir("jar-version").join("Mindustry.jar");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        zip.start_file("version.properties", Default::default())
+            .unwrap();
+        zip.write_all(STABLE.as_bytes()).unwrap();
+        zip.finish().unwrap();
+        assert_eq!(
+            jar_version(&path),
+            Some(GameVersion::Release {
+                major: 126,
+                minor: 2
+            })
+        );
+    }
+
+    #[test]
+    fn gives_every_jar_to_one_release() {
+        let dir = temp_dir("mark");
+        let install_dir = dir.to_str().unwrap();
+        let mut releases = vec![
+            release("v126.2", "GitHub"),
+            release("v126.2-rc1", "GitHub"),
+            release("v126.2", "Fork"),
+            release("v104", "GitHub"),
+        ];
+        let mut jars = vec![
+            jar(dir.join("Mindustry.jar"), "v126.2"),
+            jar(dir.join("old.jar"), "v104"),
+        ];
+        mark(
+            &mut releases,
+            install_dir,
+            &mut jars,
+            &InstallDatabase::default(),
+        );
+        // three releases are 126.2, the jar could be any of them
+        assert!(releases[..3].iter().all(|release| !release.installed));
+        assert_eq!(releases[3].jar, Some(dir.join("old.jar")));
+
+        let mut jars = vec![jar(
+            launcher::jar_path(install_dir, "GitHub", "v126.2"),
+            "v126.2",
+        )];
+        mark(
+            &mut releases,
+            install_dir,
+            &mut jars,
+            &InstallDatabase::default(),
+        );
+        let installed: Vec<bool> = releases.iter().map(|release| release.installed).collect();
+        assert_eq!(installed, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn marks_pages_like_the_whole_list() {
+        let dir = temp_dir("mark-pages");
+        let install_dir = dir.to_str().unwrap();
+        let mut jars = vec![jar(dir.join("Mindustry.jar"), "v126.2")];
+        let database = InstallDatabase::default();
+
+        // the official page comes first, alone it has the only 126.2
+        let mut loaded = vec![release("v126.2", "GitHub"), release("v104", "GitHub")];
+        mark(&mut loaded, install_dir, &mut jars, &database);
+        assert!(loaded[0].installed);
+
+        // the fork page makes the version ambiguous, the jar is nobody's again
+        loaded.push(release("v126.2", "Fork"));
+        mark(&mut loaded, install_dir, &mut jars, &database);
+        let mut whole = vec![
+            release("v126.2", "GitHub"),
+            release("v104", "GitHub"),
+            release("v126.2", "Fork"),
+        ];
+        mark(&mut whole, install_dir, &mut jars, &database);
+        let owners = |releases: &[Release]| -> Vec<bool> {
+            releases.iter().map(|release| release.installed).collect()
+        };
+        assert_eq!(owners(&loaded), vec![false, false, false]);
+        assert_eq!(owners(&loaded), owners(&whole));
+    }
+}
@@ -0,0 +1,329 @@
+use super::installed;
+use super::paths;
+use crate::models::instance::{Instance, InstancesFile};
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Lives next to `Settings.toml`
+pub const INSTANCES_FILE: &str = "Instances.toml";
+
+/// Instances created by the launcher are placed here
+pub fn instances_dir() -> PathBuf {
+    paths::data_dir().join("instances")
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn dir_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "instance".to_string()
+    } else {
+        name
+    }
+}
+
+/// A fresh directory under `instances/`, numbered when the name is taken
+fn new_instance_path(name: &str) -> PathBuf {
+    let base = dir_name(name);
+    let mut path = instances_dir().join(&base);
+    let mut number = 2;
+    while path.exists() {
+        path = instances_dir().join(format!("{}-{}", base, number));
+        number += 1;
+    }
+    path
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Error> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_link(&entry.path(), &target)?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Links are copied as links, a linked mods folder is shared instead of duplicated
+#[cfg(unix)]
+fn copy_link(from: &Path, to: &Path) -> Result<(), Error> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_link(from: &Path, to: &Path) -> Result<(), Error> {
+    let target = fs::read_link(from)?;
+    let linked = if from.is_dir() {
+        std::os::windows::fs::symlink_dir(&target, to)
+    } else {
+        std::os::windows::fs::symlink_file(&target, to)
+    };
+    // creating links needs developer mode or admin rights on Windows
+    linked.map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("cannot copy link {}: {}", from.display(), e),
+        )
+    })
+}
+
+impl InstancesFile {
+    /// Reads `Instances.toml`. Without it a single "default" instance is made from the old `path` setting.
+    pub fn load(default_path: &str) -> Result<Self, Error> {
+        let data = match fs::read_to_string(INSTANCES_FILE) {
+            Ok(data) => data,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {
+                return Ok(InstancesFile {
+                    selected: "default".to_string(),
+                    instance: vec![Instance {
+                        name: "default".to_string(),
+                        path: default_path.to_string(),
+                    }],
+                })
+            }
+            Err(e) => return Err(e),
+        };
+        let mut file: InstancesFile =
+            toml::from_str(&data).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        if file.instance.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} has no instances", INSTANCES_FILE),
+            ));
+        }
+        if file.find(&file.selected).is_none() {
+            file.selected = file.instance[0].name.clone();
+        }
+        Ok(file)
+    }
+
+    /// Synced before the rename like the install database, a crash keeps the old or the new list
+    pub fn save(&self) -> Result<(), Error> {
+        let data = toml::to_string_pretty(self).map_err(Error::other)?;
+        let temp = format!("{}.tmp", INSTANCES_FILE);
+        let mut file = File::create(&temp)?;
+        file.write_all(data.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp, INSTANCES_FILE)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Instance> {
+        self.instance.iter().find(|instance| instance.name == name)
+    }
+
+    pub fn current(&self) -> &Instance {
+        self.find(&self.selected).unwrap_or(&self.instance[0])
+    }
+
+    pub fn current_mut(&mut self) -> &mut Instance {
+        let index = self
+            .instance
+            .iter()
+            .position(|instance| instance.name == self.selected)
+            .unwrap_or(0);
+        &mut self.instance[index]
+    }
+
+    pub fn select(&mut self, name: &str) {
+        if self.find(name).is_some() {
+            self.selected = name.to_string();
+        }
+    }
+
+    fn check_new_name(&self, name: &str) -> Result<(), Error> {
+        if name.trim().is_empty() {
+            return Err(invalid("instance name is empty".to_string()));
+        }
+        if self.find(name).is_some() {
+            return Err(invalid(format!("instance {} already exists", name)));
+        }
+        Ok(())
+    }
+
+    pub fn create(&mut self, name: &str) -> Result<(), Error> {
+        self.check_new_name(name)?;
+        let path = new_instance_path(name);
+        fs::create_dir_all(path.join("data"))?;
+        self.instance.push(Instance {
+            name: name.to_string(),
+            path: path.to_string_lossy().to_string(),
+        });
+        self.selected = name.to_string();
+        Ok(())
+    }
+
+    /// Only the name changes, the directory stays where it is
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<(), Error> {
+        self.check_new_name(new_name)?;
+        let instance = self
+            .instance
+            .iter_mut()
+            .find(|instance| instance.name == name)
+            .ok_or_else(|| invalid(format!("no instance {}", name)))?;
+        instance.name = new_name.to_string();
+        if self.selected == name {
+            self.selected = new_name.to_string();
+        }
+        Ok(())
+    }
+
+    /// Copies jars and game data into a new instance
+    pub fn clone_instance(&mut self, name: &str, new_name: &str) -> Result<(), Error> {
+        self.check_new_name(new_name)?;
+        let source = self
+            .find(name)
+            .ok_or_else(|| invalid(format!("no instance {}", name)))?
+            .clone();
+        let path = new_instance_path(new_name);
+        if path.starts_with(&source.path) {
+            return Err(invalid(format!(
+                "{} contains the instances directory and can not be cloned",
+                source.path
+            )));
+        }
+        copy_dir(Path::new(&source.path), &path)?;
+        self.instance.push(Instance {
+            name: new_name.to_string(),
+            path: path.to_string_lossy().to_string(),
+        });
+        self.selected = new_name.to_string();
+        Ok(())
+    }
+
+    /// True when the launcher created the directory of the instance, only then deleting it removes files
+    pub fn is_managed(&self, name: &str) -> bool {
+        self.managed_in(&instances_dir(), name)
+    }
+
+    /// The directory has to lie below `dir` and must neither hold nor be inside another instance
+    fn managed_in(&self, dir: &Path, name: &str) -> bool {
+        let path = match self.find(name) {
+            Some(instance) => Path::new(&instance.path),
+            None => return false,
+        };
+        path.starts_with(dir)
+            && path != dir
+            && !path
+                .components()
+                .any(|component| component == Component::ParentDir)
+            && self
+                .instance
+                .iter()
+                .filter(|other| other.name != name)
+                .all(|other| {
+                    let other = Path::new(&other.path);
+                    !other.starts_with(path) && !path.starts_with(other)
+                })
+    }
+
+    /// Removes the instance. Its files are deleted only when the launcher created the directory.
+    pub fn delete(&mut self, name: &str) -> Result<(), Error> {
+        if self.instance.len() <= 1 {
+            return Err(invalid("the last instance can not be deleted".to_string()));
+        }
+        let index = self
+            .instance
+            .iter()
+            .position(|instance| instance.name == name)
+            .ok_or_else(|| invalid(format!("no instance {}", name)))?;
+        let path = PathBuf::from(&self.instance[index].path);
+        if self.is_managed(name) && path.exists() {
+            let jars = installed::scan(&self.instance[index].path);
+            fs::remove_dir_all(&path)?;
+            // a stale record would match whatever jar is placed at the same path later
+            for jar in jars {
+                if let Err(e) = installed::forget(&jar.path) {
+                    println!("cannot forget {}: {}", jar.path.display(), e);
+                }
+            }
+        }
+        self.instance.remove(index);
+        if self.selected == name {
+            self.selected = self.instance[0].name.clone();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::temp_dir;
+
+    #[cfg(unix)]
+    #[test]
+    fn copies_links_as_links() {
+        let dir = temp_dir("copy-dir");
+        let from = dir.join("from");
+        fs::create_dir_all(from.join("data").join("saves")).unwrap();
+        fs::write(from.join("data").join("saves").join("0.msav"), "save").unwrap();
+        std::os::unix::fs::symlink("data/saves", from.join("saves-link")).unwrap();
+
+        let to = dir.join("to");
+        copy_dir(&from, &to).unwrap();
+        assert_eq!(
+            fs::read_to_string(to.join("data").join("saves").join("0.msav")).unwrap(),
+            "save"
+        );
+        assert_eq!(
+            fs::read_link(to.join("saves-link")).unwrap(),
+            Path::new("data/saves")
+        );
+    }
+
+    #[test]
+    fn manages_only_own_directories_below_instances() {
+        let dir = Path::new("/data/instances");
+        let file = |instances: &[(&str, &str)]| InstancesFile {
+            selected: instances[0].0.to_string(),
+            instance: instances
+                .iter()
+                .map(|(name, path)| Instance {
+                    name: name.to_string(),
+                    path: path.to_string(),
+                })
+                .collect(),
+        };
+        let instances = file(&[
+            ("a", "/data/instances/a"),
+            ("outside", "/home/user/mindustry"),
+            ("escape", "/data/instances/../../home/user"),
+            ("shared", "/data/instances/shared"),
+            ("inner", "/data/instances/shared/inner"),
+            ("twin", "/data/instances/twin"),
+            ("twin copy", "/data/instances/twin/"),
+        ]);
+        assert!(instances.managed_in(dir, "a"));
+        assert!(!instances.managed_in(dir, "outside"));
+        assert!(!instances.managed_in(dir, "escape"));
+        assert!(!instances.managed_in(dir, "shared"));
+        assert!(!instances.managed_in(dir, "inner"));
+        assert!(!instances.managed_in(dir, "twin"));
+        assert!(!instances.managed_in(dir, "missing"));
+
+        // the directory itself holds every instance
+        let instances = file(&[("root", "/data/instances"), ("a", "/data/instances/a")]);
+        assert!(!instances.managed_in(dir, "root"));
+        assert!(!instances.managed_in(dir, "a"));
+    }
+}
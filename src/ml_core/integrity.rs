@@ -0,0 +1,242 @@
+use super::http_tools;
+use super::installed;
+use crate::models::version::ReleaseAsset;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read};
+use std::path::Path;
+
+/// Release assets listing the hashes of all the others, `sha256sum` format
+const CHECKSUM_LISTS: [&str; 4] = [
+    "sha256sums",
+    "sha256sums.txt",
+    "checksums.txt",
+    "checksums.sha256",
+];
+
+/// Where the SHA-256 of an asset comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Checksum {
+    /// Published with the asset by the API or the manifest
+    Sha256(String),
+    /// A checksum file among the release assets, `name` is the asset to look up in it
+    File { url: String, name: String },
+}
+
+impl Checksum {
+    /// The SHA-256 of `asset`: its own, a `<name>.sha256` asset, or a checksum list of the release.
+    /// `None` when the source does not publish one, the download is then checked by size and content only.
+    pub fn of(asset: &ReleaseAsset, assets: &[ReleaseAsset]) -> Option<Checksum> {
+        if let Some(sha256) = asset.sha256.as_ref().filter(|sha256| !sha256.is_empty()) {
+            return Some(Checksum::Sha256(sha256.to_lowercase()));
+        }
+        let own = format!("{}.sha256", asset.name).to_lowercase();
+        let file = assets
+            .iter()
+            .find(|file| file.name.to_lowercase() == own)
+            .or_else(|| {
+                assets
+                    .iter()
+                    .find(|file| CHECKSUM_LISTS.contains(&file.name.to_lowercase().as_str()))
+            })?;
+        Some(Checksum::File {
+            url: file.download_url.clone(),
+            name: asset.name.clone(),
+        })
+    }
+
+    /// Downloads the checksum file when needed. `None` when the file does not list the asset,
+    /// the download is then checked like one without a checksum.
+    pub fn resolve(&self) -> Result<Option<String>, Error> {
+        let (url, name) = match self {
+            Checksum::Sha256(sha256) => return Ok(Some(sha256.clone())),
+            Checksum::File { url, name } => (url, name),
+        };
+        let mut data = String::new();
+        http_tools::load_file(url)?.read_to_string(&mut data)?;
+        let sha256 = parse_checksums(&data, name);
+        if sha256.is_none() {
+            println!("{} has no checksum for {}", url, name);
+        }
+        Ok(sha256)
+    }
+}
+
+/// Finds the hash of `name` in `sha256sum` output: "<hash>  <file name>" per line,
+/// `*` marks binary mode. A single hash without a name is taken as is.
+fn parse_checksums(data: &str, name: &str) -> Option<String> {
+    let mut lines = data.lines().filter(|line| !line.trim().is_empty());
+    let found = lines.clone().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let file = parts.next()?.trim_start_matches('*');
+        if file == name || file.rsplit('/').next() == Some(name) {
+            Some(hash)
+        } else {
+            None
+        }
+    });
+    let hash = match found {
+        Some(hash) => hash,
+        None => {
+            let only = lines.next()?;
+            if lines.next().is_some() || only.split_whitespace().count() != 1 {
+                return None;
+            }
+            only.trim()
+        }
+    };
+    Some(hash.to_lowercase())
+}
+
+fn corrupted(message: String) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("corrupted download: {}", message),
+    )
+}
+
+pub fn verify_sha256(path: &Path, expected: &str) -> Result<(), Error> {
+    let actual = installed::sha256_file(path)?;
+    if actual != expected {
+        return Err(corrupted(format!(
+            "checksum mismatch: expected {}, got {}",
+            expected, actual
+        )));
+    }
+    Ok(())
+}
+
+/// Opens the jar like Java would: the central directory has to be readable
+/// and `META-INF/MANIFEST.MF` has to name the class to start
+pub fn verify_jar(path: &Path) -> Result<(), Error> {
+    let mut zip = zip::ZipArchive::new(File::open(path)?)
+        .map_err(|e| corrupted(format!("not a readable jar: {}", e)))?;
+    let mut manifest = String::new();
+    zip.by_name("META-INF/MANIFEST.MF")
+        .map_err(|_| corrupted("META-INF/MANIFEST.MF is missing".to_string()))?
+        .read_to_string(&mut manifest)
+        .map_err(|e| corrupted(format!("cannot read META-INF/MANIFEST.MF: {}", e)))?;
+    let main_class = manifest.lines().any(|line| {
+        let mut parts = line.splitn(2, ':');
+        parts.next().map(str::trim) == Some("Main-Class")
+            && parts.next().is_some_and(|class| !class.trim().is_empty())
+    });
+    if !main_class {
+        return Err(corrupted("the jar manifest has no Main-Class".to_string()));
+    }
+    Ok(())
+}
+
+/// Every check of a downloaded jar, the size is checked by the download itself
+pub fn verify(path: &Path, sha256: Option<&str>) -> Result<(), Error> {
+    if let Some(expected) = sha256 {
+        verify_sha256(path, expected)?;
+    }
+    verify_jar(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::{temp_dir, Response, TestServer};
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    fn write_jar(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let path = temp_dir(&format!("integrity-{}", name)).join("game.jar");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        for (file, content) in files {
+            zip.start_file(*file, Default::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    fn asset(name: &str, url: &str) -> ReleaseAsset {
+        ReleaseAsset {
+            name: name.to_string(),
+            size: 1,
+            download_url: url.to_string(),
+            sha256: None,
+        }
+    }
+
+    #[test]
+    fn parses_checksum_files() {
+        let list = "0A1B  Mindustry.jar\n2c3d *server-release.jar\n\n4e5f  dist/Other.jar\n";
+        assert_eq!(
+            parse_checksums(list, "Mindustry.jar").as_deref(),
+            Some("0a1b")
+        );
+        assert_eq!(
+            parse_checksums(list, "server-release.jar").as_deref(),
+            Some("2c3d")
+        );
+        assert_eq!(parse_checksums(list, "Other.jar").as_deref(), Some("4e5f"));
+        assert_eq!(parse_checksums(list, "Missing.jar"), None);
+        assert_eq!(
+            parse_checksums("ABCD\n", "Mindustry.jar").as_deref(),
+            Some("abcd")
+        );
+        assert_eq!(parse_checksums("", "Mindustry.jar"), None);
+    }
+
+    #[test]
+    fn falls_back_when_the_list_misses_the_asset() {
+        let server = TestServer::start(|_| Response::ok("0a1b  other.zip\n"));
+        let assets = vec![
+            asset("Mindustry.jar", &server.url("/Mindustry.jar")),
+            asset("checksums.txt", &server.url("/checksums.txt")),
+        ];
+        let checksum = Checksum::of(&assets[0], &assets).unwrap();
+        assert_eq!(
+            checksum,
+            Checksum::File {
+                url: server.url("/checksums.txt"),
+                name: "Mindustry.jar".to_string()
+            }
+        );
+        assert_eq!(checksum.resolve().unwrap(), None);
+        assert_eq!(Checksum::of(&assets[0], &assets[..1]), None);
+    }
+
+    #[test]
+    fn accepts_a_runnable_jar() {
+        let path = write_jar(
+            "good",
+            &[(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\r\nMain-Class: mindustry.desktop.DesktopLauncher\r\n\r\n",
+            )],
+        );
+        verify_jar(&path).unwrap();
+        let sha256 = installed::sha256_file(&path).unwrap();
+        verify(&path, Some(&sha256)).unwrap();
+        assert!(verify(&path, Some("00")).is_err());
+    }
+
+    #[test]
+    fn rejects_broken_jars() {
+        let good = write_jar(
+            "truncated",
+            &[("META-INF/MANIFEST.MF", "Main-Class: a.B\n")],
+        );
+        let data = fs::read(&good).unwrap();
+        fs::write(&good, &data[..data.len() / 2]).unwrap();
+        let no_manifest = write_jar("no-manifest", &[("version.properties", "build=146\n")]);
+        let no_main_class = write_jar(
+            "no-main-class",
+            &[(
+                "META-INF/MANIFEST.MF",
+                "Manifest-Version: 1.0\nMain-Class:\n",
+            )],
+        );
+        for path in [good, no_manifest, no_main_class].iter() {
+            let error = verify_jar(path).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+            assert!(error.to_string().starts_with("corrupted download"));
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use super::paths;
+use super::runtimes;
+use crate::models::game_version::GameVersion;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(windows)]
+pub const JAVA_BINARY: &str = "java.exe";
+#[cfg(not(windows))]
+pub const JAVA_BINARY: &str = "java";
+
+const SELECTION_FILE: &str = "java.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JavaRuntime {
+    pub path: PathBuf,
+    pub vendor: String,
+    pub version: String,
+    pub major: u32,
+}
+
+/// Which runtime is used for launching: one global choice and optional overrides by `tag_name`
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeSelection {
+    pub global: Option<PathBuf>,
+    pub per_version: HashMap<String, PathBuf>,
+}
+
+impl RuntimeSelection {
+    pub fn load() -> Self {
+        fs::read_to_string(paths::data_dir().join(SELECTION_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let data = serde_json::to_string_pretty(self)?;
+        fs::write(paths::data_dir().join(SELECTION_FILE), data)
+    }
+
+    /// Picks the runtime for a version: per-version choice, then the global one, then the first discovered
+    pub fn resolve<'a>(
+        &self,
+        tag_name: &str,
+        runtimes: &'a [JavaRuntime],
+    ) -> Option<&'a JavaRuntime> {
+        let find = |path: &PathBuf| runtimes.iter().find(|runtime| &runtime.path == path);
+        self.per_version
+            .get(tag_name)
+            .and_then(find)
+            .or_else(|| self.global.as_ref().and_then(find))
+            .or_else(|| runtimes.first())
+    }
+}
+
+/// Minimal Java major version a Mindustry build needs.
+/// Releases from v136 onward are compiled for Java 17, older ones run on Java 8.
+/// Bleeding-edge builds are numbered on their own and current ones need Java 17.
+pub fn required_major(tag_name: &str) -> u32 {
+    match GameVersion::parse(tag_name) {
+        Some(GameVersion::Release { major, .. }) if major >= 136 => 17,
+        Some(GameVersion::Build(_)) => 17,
+        _ => 8,
+    }
+}
+
+/// Parses the major number from strings like `1.8.0_251`, `11.0.7` or `17-ea`
+fn parse_major(version: &str) -> Option<u32> {
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let first = parts.next()?.parse::<u32>().ok()?;
+    if first == 1 {
+        parts.next()?.parse::<u32>().ok()
+    } else {
+        Some(first)
+    }
+}
+
+fn parse_vendor(output: &str) -> String {
+    const VENDORS: [(&str, &str); 9] = [
+        ("Temurin", "Eclipse Temurin"),
+        ("AdoptOpenJDK", "AdoptOpenJDK"),
+        ("Zulu", "Azul Zulu"),
+        ("Corretto", "Amazon Corretto"),
+        ("GraalVM", "GraalVM"),
+        ("Microsoft", "Microsoft"),
+        ("Java(TM)", "Oracle"),
+        ("OpenJ9", "IBM Semeru"),
+        ("OpenJDK", "OpenJDK"),
+    ];
+    VENDORS
+        .iter()
+        .find(|(needle, _)| output.contains(needle))
+        .map(|(_, vendor)| vendor.to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Parses the output of `java -version` into (vendor, version, major)
+pub fn parse_version_output(output: &str) -> Option<(String, String, u32)> {
+    let first_line = output.lines().find(|line| line.contains(" version "))?;
+    let version = first_line.split('"').nth(1)?.to_string();
+    let major = parse_major(&version)?;
+    Some((parse_vendor(output), version, major))
+}
+
+pub fn probe(path: &Path) -> Result<JavaRuntime, Error> {
+    let output = Command::new(path).arg("-version").output()?;
+    // `java -version` prints to stderr, some builds print to stdout
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stderr),
+        String::from_utf8_lossy(&output.stdout)
+    );
+    match parse_version_output(&text) {
+        Some((vendor, version, major)) => Ok(JavaRuntime {
+            path: path.to_path_buf(),
+            vendor,
+            version,
+            major,
+        }),
+        None => Err(Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("cannot parse java -version output of {:?}", path),
+        )),
+    }
+}
+
+/// `configured` may point to the java binary itself or to a JDK/JRE home
+fn configured_candidate(configured: &str) -> PathBuf {
+    let path = PathBuf::from(configured);
+    if path.is_dir() {
+        path.join("bin").join(JAVA_BINARY)
+    } else {
+        path
+    }
+}
+
+fn candidates(configured: Option<&str>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Some(configured) = configured.filter(|configured| !configured.is_empty()) {
+        candidates.push(configured_candidate(configured));
+    }
+
+    if let Some(java_home) = env::var_os("JAVA_HOME") {
+        candidates.push(PathBuf::from(java_home).join("bin").join(JAVA_BINARY));
+    }
+
+    if let Some(path) = env::var_os("PATH") {
+        for dir in env::split_paths(&path) {
+            candidates.push(dir.join(JAVA_BINARY));
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir("/usr/lib/jvm") {
+        let mut jvms: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join("bin").join(JAVA_BINARY))
+            .collect();
+        jvms.sort();
+        candidates.extend(jvms);
+    }
+
+    candidates.extend(runtimes::managed_java_binaries());
+
+    candidates
+}
+
+/// Finds every usable Java install. Symlinked duplicates (e.g. /usr/bin/java) are reported once.
+pub fn discover(configured: Option<&str>) -> Vec<JavaRuntime> {
+    let mut seen = Vec::new();
+    let mut runtimes = Vec::new();
+
+    for candidate in candidates(configured) {
+        if !candidate.is_file() {
+            continue;
+        }
+        let canonical = fs::canonicalize(&candidate).unwrap_or_else(|_| candidate.clone());
+        if seen.contains(&canonical) {
+            continue;
+        }
+        seen.push(canonical);
+
+        match probe(&candidate) {
+            Ok(runtime) => runtimes.push(runtime),
+            Err(e) => println!("skipping java candidate {:?}: {}", candidate, e),
+        }
+    }
+    runtimes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_java_a_version_needs() {
+        assert_eq!(required_major("v126.2"), 8);
+        assert_eq!(required_major("v135"), 8);
+        assert_eq!(required_major("v136"), 17);
+        assert_eq!(required_major("v146"), 17);
+        assert_eq!(required_major("146.1-rc1"), 17);
+        assert_eq!(required_major("25872"), 17);
+        assert_eq!(required_major("Build 25872"), 17);
+        assert_eq!(required_major("custom-fork"), 8);
+    }
+
+    #[test]
+    fn parses_java_versions() {
+        assert_eq!(parse_major("1.8.0_251"), Some(8));
+        assert_eq!(parse_major("11.0.7"), Some(11));
+        assert_eq!(parse_major("17-ea"), Some(17));
+        let output = "openjdk version \"17.0.2\" 2022-01-18\n\
+            OpenJDK Runtime Environment Temurin-17.0.2+8 (build 17.0.2+8)\n";
+        let (vendor, version, major) = parse_version_output(output).unwrap();
+        assert_eq!(vendor, "Eclipse Temurin");
+        assert_eq!(version, "17.0.2");
+        assert_eq!(major, 17);
+    }
+}
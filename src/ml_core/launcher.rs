@@ -0,0 +1,193 @@
+use super::download;
+use super::installed;
+use super::java::JavaRuntime;
+use super::process::GameProcess;
+use super::profiles;
+use super::sources;
+use crate::models::game_version::GameVersion;
+use crate::models::install::InstallDatabase;
+use crate::models::instance::Instance;
+use crate::models::profile::{Channel, LaunchProfile};
+use crate::models::version::{Release, ReleaseAsset};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Path of the jar for a release inside the installation directory. The default sources keep
+/// plain `<tag>.jar` names, other sources get their name in front: a fork's `v146` must not
+/// overwrite the official one.
+pub fn jar_path(install_dir: &str, source: &str, tag_name: &str) -> PathBuf {
+    let name = if sources::is_default(source) {
+        tag_name.to_string()
+    } else {
+        format!("{}-{}", source, tag_name)
+    };
+    Path::new(install_dir).join(format!("{}.jar", download::sanitize_file_name(&name)))
+}
+
+/// Releases for the jars already in the installation directory, what the list shows when offline.
+/// The tag comes from the install database or the jar name, the version from the jar itself.
+pub fn installed_releases(install_dir: &str, database: &InstallDatabase) -> Vec<Release> {
+    installed::scan(install_dir)
+        .into_iter()
+        .filter_map(|jar| {
+            let tag_name = match database.find(&jar.path) {
+                Some(record) => record.tag_name.clone(),
+                None => jar.path.file_stem()?.to_string_lossy().to_string(),
+            };
+            let version = jar.version.or_else(|| GameVersion::parse(&tag_name));
+            let channel = match version {
+                Some(GameVersion::Build(_)) => Channel::BleedingEdge,
+                _ => Channel::Stable,
+            };
+            let asset = ReleaseAsset {
+                name: jar.path.file_name()?.to_string_lossy().to_string(),
+                size: jar.size,
+                download_url: format!("file://{}", jar.path.display()),
+                sha256: None,
+            };
+            Some(Release {
+                name: tag_name.clone(),
+                tag_name,
+                version,
+                published_at: fs::metadata(&jar.path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .map(DateTime::<Utc>::from),
+                assets: vec![asset.clone()],
+                desktop: Some(asset),
+                installed: true,
+                jar: Some(jar.path),
+                channel,
+                source: "Installed".to_string(),
+                ..Release::default()
+            })
+        })
+        .collect()
+}
+
+/// Starts `<java> <jvm args> -jar <jar> <game args>` for the given version.
+/// The process runs on its own, its output is collected by the returned handle.
+pub fn launch(
+    java: Option<&JavaRuntime>,
+    instance: &Instance,
+    tag_name: &str,
+    jar: &Path,
+    profile: &LaunchProfile,
+) -> Result<GameProcess, Error> {
+    if !jar.is_file() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("version {} is not installed", tag_name),
+        ));
+    }
+
+    let working_dir = profile
+        .working_dir
+        .as_deref()
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or(&instance.path);
+
+    // the game keeps its data in the OS app data folder (XDG_DATA_HOME, APPDATA), point it into the instance
+    let data_root = instance.data_root();
+    fs::create_dir_all(&data_root)?;
+
+    // without a discovered runtime let the OS look `java` up in PATH
+    let java_path = java
+        .map(|runtime| runtime.path.clone())
+        .unwrap_or_else(|| PathBuf::from("java"));
+
+    let mut command = Command::new(java_path);
+    if cfg!(target_os = "macos") {
+        // macOS has no data directory variable, the game builds it from the home folder
+        command.arg(format!("-Duser.home={}", data_root.display()));
+    }
+    command
+        .args(profiles::jvm_args(profile))
+        .arg("-jar")
+        .arg(jar)
+        .args(&profile.game_args)
+        .env("XDG_DATA_HOME", &data_root)
+        .env("APPDATA", &data_root)
+        .envs(&profile.env)
+        .current_dir(working_dir);
+
+    GameProcess::spawn(
+        command,
+        tag_name,
+        jar,
+        profile,
+        java,
+        instance.game_data_dir(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::temp_dir;
+    use crate::models::install::InstallRecord;
+
+    #[test]
+    fn names_jars_by_source_and_tag() {
+        let dir = "/games";
+        assert_eq!(
+            jar_path(dir, "Mindustry", "v146"),
+            Path::new("/games/v146.jar")
+        );
+        assert_eq!(
+            jar_path(dir, "Bleeding edge", "25872"),
+            Path::new("/games/25872.jar")
+        );
+        assert_eq!(
+            jar_path(dir, "Fork", "v146"),
+            Path::new("/games/Fork-v146.jar")
+        );
+        assert_eq!(
+            jar_path(dir, "Team/Fork", "v1:2"),
+            Path::new("/games/Team_Fork-v1_2.jar")
+        );
+    }
+
+    #[test]
+    fn lists_installed_jars_for_offline_use() {
+        let dir = temp_dir("launcher-installed");
+        fs::write(dir.join("v146.jar"), "jar").unwrap();
+        fs::write(dir.join("25872.jar"), "jar").unwrap();
+        fs::write(dir.join("Mindustry.jar"), "jar").unwrap();
+        fs::write(dir.join("notes.txt"), "text").unwrap();
+        let mut database = InstallDatabase::default();
+        database.installs.push(InstallRecord {
+            tag_name: "v140".to_string(),
+            source: "Anuken/Mindustry".to_string(),
+            repo: None,
+            asset_name: "Mindustry.jar".to_string(),
+            path: dir.join("Mindustry.jar"),
+            size: 3,
+            sha256: String::new(),
+            installed_at: Utc::now(),
+            last_played: None,
+            play_time: 0,
+        });
+
+        let mut releases = installed_releases(dir.to_str().unwrap(), &database);
+        releases.sort_by(|a, b| a.tag_name.cmp(&b.tag_name));
+        let tags: Vec<_> = releases
+            .iter()
+            .map(|release| release.tag_name.as_str())
+            .collect();
+        assert_eq!(tags, ["25872", "v140", "v146"]);
+        assert_eq!(releases[0].channel, Channel::BleedingEdge);
+        assert_eq!(releases[1].jar, Some(dir.join("Mindustry.jar")));
+        assert_eq!(
+            releases[2].version,
+            Some(GameVersion::Release {
+                major: 146,
+                minor: 0
+            })
+        );
+        assert!(releases.iter().all(|release| release.installed));
+    }
+}
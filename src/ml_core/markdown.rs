@@ -0,0 +1,330 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    Plain,
+    Bold,
+    Code,
+    Heading,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+    /// Target of a link, the text is what gets shown
+    pub link: Option<String>,
+}
+
+/// One wrapped line, ready to draw
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Line {
+    /// Offset in characters, used by list items
+    pub indent: usize,
+    pub spans: Vec<Span>,
+}
+
+fn push_span(spans: &mut Vec<Span>, text: &str, style: Style, link: Option<String>) {
+    if !text.is_empty() {
+        spans.push(Span {
+            text: text.to_string(),
+            style,
+            link,
+        });
+    }
+}
+
+/// Splits a line into styled spans: `**bold**`, `__bold__`, `` `code` ``, `[text](url)` and `![alt](url)`
+pub fn parse_inline(text: &str, base: Style) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let parsed = if rest.starts_with("**") || rest.starts_with("__") {
+            let marker = &rest[..2];
+            rest[2..].find(marker).map(|end| {
+                let inner = &rest[2..2 + end];
+                (
+                    Span {
+                        text: inner.to_string(),
+                        style: Style::Bold,
+                        link: None,
+                    },
+                    4 + end,
+                )
+            })
+        } else if c == '`' {
+            rest[1..].find('`').map(|end| {
+                (
+                    Span {
+                        text: rest[1..1 + end].to_string(),
+                        style: Style::Code,
+                        link: None,
+                    },
+                    2 + end,
+                )
+            })
+        } else if c == '[' || rest.starts_with("![") {
+            let start = if c == '[' { 1 } else { 2 };
+            rest[start..].find("](").and_then(|label_end| {
+                let label = &rest[start..start + label_end];
+                let url_start = start + label_end + 2;
+                rest[url_start..].find(')').map(|url_end| {
+                    (
+                        Span {
+                            text: label.to_string(),
+                            style: base,
+                            link: Some(rest[url_start..url_start + url_end].to_string()),
+                        },
+                        url_start + url_end + 1,
+                    )
+                })
+            })
+        } else {
+            None
+        };
+
+        match parsed {
+            Some((span, length)) => {
+                push_span(&mut spans, &plain, base, None);
+                plain.clear();
+                if !span.text.is_empty() {
+                    spans.push(span);
+                }
+                rest = &rest[length..];
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    push_span(&mut spans, &plain, base, None);
+    spans
+}
+
+/// Breaks spans into lines of at most `width` characters. The first line starts at `first_indent`,
+/// the following ones at `indent` so list items hang after their bullet.
+fn wrap(spans: Vec<Span>, first_indent: usize, indent: usize, width: usize, lines: &mut Vec<Line>) {
+    let width = width.saturating_sub(indent).max(10);
+    let mut line = Line {
+        indent: first_indent,
+        spans: Vec::new(),
+    };
+    let mut used = 0;
+    for span in spans {
+        // code keeps its spaces, everything else wraps on words
+        let words: Vec<String> = if span.style == Style::Code {
+            vec![span.text.clone()]
+        } else {
+            span.text
+                .split_inclusive(' ')
+                .map(|word| word.to_string())
+                .collect()
+        };
+        for mut word in words {
+            loop {
+                let length = word.chars().count();
+                if used + length <= width {
+                    used += length;
+                    line.spans.push(Span {
+                        text: word,
+                        style: span.style,
+                        link: span.link.clone(),
+                    });
+                    break;
+                }
+                if used > 0 {
+                    lines.push(std::mem::replace(
+                        &mut line,
+                        Line {
+                            indent,
+                            spans: Vec::new(),
+                        },
+                    ));
+                    used = 0;
+                    word = word.trim_start().to_string();
+                    continue;
+                }
+                // a single word longer than the line is cut
+                let head: String = word.chars().take(width).collect();
+                word = word.chars().skip(width).collect();
+                line.spans.push(Span {
+                    text: head,
+                    style: span.style,
+                    link: span.link.clone(),
+                });
+                lines.push(std::mem::replace(
+                    &mut line,
+                    Line {
+                        indent,
+                        spans: Vec::new(),
+                    },
+                ));
+            }
+        }
+    }
+    if !line.spans.is_empty() {
+        lines.push(line);
+    }
+}
+
+/// Lays out a Markdown document for a column `width` characters wide.
+/// Supports just what release notes use: headings, lists, bold, code spans, code blocks and links.
+pub fn layout(markdown: &str, width: usize) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for raw in markdown.lines() {
+        let trimmed = raw.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            let text: String = raw.trim_end().chars().take(width).collect();
+            lines.push(Line {
+                indent: 2,
+                spans: vec![Span {
+                    text,
+                    style: Style::Code,
+                    link: None,
+                }],
+            });
+            continue;
+        }
+        if trimmed.is_empty() {
+            // one empty line between blocks is enough
+            if lines.last().is_some_and(|line| !line.spans.is_empty()) {
+                lines.push(Line::default());
+            }
+            continue;
+        }
+
+        let depth = (raw.len() - raw.trim_start().len()) / 2;
+        let heading = trimmed.chars().take_while(|c| *c == '#').count();
+        if heading > 0 && heading <= 6 && trimmed[heading..].starts_with(' ') {
+            wrap(
+                parse_inline(trimmed[heading..].trim(), Style::Heading),
+                0,
+                0,
+                width,
+                &mut lines,
+            );
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find(|bullet| trimmed.starts_with(*bullet))
+            .map(|bullet| &trimmed[bullet.len()..])
+        {
+            let mut spans = vec![Span {
+                text: "• ".to_string(),
+                style: Style::Plain,
+                link: None,
+            }];
+            spans.extend(parse_inline(item, Style::Plain));
+            wrap(spans, depth * 2, depth * 2 + 2, width, &mut lines);
+        } else {
+            wrap(parse_inline(trimmed, Style::Plain), 0, 0, width, &mut lines);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, style: Style, link: Option<&str>) -> Span {
+        Span {
+            text: text.to_string(),
+            style,
+            link: link.map(str::to_string),
+        }
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_inline_markup() {
+        assert_eq!(
+            parse_inline(
+                "a **b** __c__ `d e` [f](http://g) ![img](h.png)",
+                Style::Plain
+            ),
+            vec![
+                span("a ", Style::Plain, None),
+                span("b", Style::Bold, None),
+                span(" ", Style::Plain, None),
+                span("c", Style::Bold, None),
+                span(" ", Style::Plain, None),
+                span("d e", Style::Code, None),
+                span(" ", Style::Plain, None),
+                span("f", Style::Plain, Some("http://g")),
+                span(" ", Style::Plain, None),
+                span("img", Style::Plain, Some("h.png")),
+            ]
+        );
+        assert_eq!(
+            parse_inline("v146 [notes](u)", Style::Heading),
+            vec![
+                span("v146 ", Style::Heading, None),
+                span("notes", Style::Heading, Some("u")),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unclosed_markers_as_text() {
+        for text in ["**open", "`tick", "[label](url", "[label] (url)", "![alt"].iter() {
+            assert_eq!(
+                parse_inline(text, Style::Plain),
+                vec![span(text, Style::Plain, None)]
+            );
+        }
+        assert_eq!(parse_inline("", Style::Plain), vec![]);
+        assert_eq!(parse_inline("****", Style::Plain), vec![]);
+    }
+
+    #[test]
+    fn lays_out_blocks() {
+        let markdown = "# Title\n\n\n- one\n  - nested\n\n```\nlet x  = 1;\n# not a heading\n```\n#nospace\n####### seven";
+        let lines = layout(markdown, 40);
+        let shown: Vec<(usize, String)> =
+            lines.iter().map(|line| (line.indent, text(line))).collect();
+        assert_eq!(
+            shown,
+            vec![
+                (0, "Title".to_string()),
+                (0, String::new()),
+                (0, "• one".to_string()),
+                (2, "• nested".to_string()),
+                (0, String::new()),
+                (2, "let x  = 1;".to_string()),
+                (2, "# not a heading".to_string()),
+                (0, "#nospace".to_string()),
+                (0, "####### seven".to_string()),
+            ]
+        );
+        assert_eq!(lines[0].spans[0].style, Style::Heading);
+        assert_eq!(lines[5].spans[0].style, Style::Code);
+    }
+
+    #[test]
+    fn wraps_words_and_cuts_long_ones() {
+        let texts = |lines: Vec<Line>| -> Vec<(usize, String)> {
+            lines.iter().map(|line| (line.indent, text(line))).collect()
+        };
+        assert_eq!(
+            texts(layout("aaa bbb ccc", 10)),
+            vec![(0, "aaa bbb ".to_string()), (0, "ccc".to_string())]
+        );
+        // list items hang after their bullet
+        assert_eq!(
+            texts(layout("- aaaa bbbb cccc", 10)),
+            vec![(0, "• aaaa ".to_string()), (2, "bbbb cccc".to_string())]
+        );
+        assert_eq!(
+            texts(layout(&"x".repeat(25), 10)),
+            vec![(0, "x".repeat(10)), (0, "x".repeat(10)), (0, "x".repeat(5)),]
+        );
+    }
+}
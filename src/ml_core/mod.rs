@@ -1 +1,21 @@
-pub mod http_tools;
\ No newline at end of file
+pub mod crash;
+pub mod download;
+pub mod download_queue;
+pub mod filter;
+pub mod http_tools;
+pub mod installed;
+pub mod instances;
+pub mod integrity;
+pub mod java;
+pub mod launcher;
+pub mod markdown;
+pub mod paths;
+pub mod process;
+pub mod profiles;
+pub mod providers;
+pub mod releases;
+pub mod runtimes;
+pub mod server;
+pub mod sources;
+#[cfg(test)]
+mod test_support;
@@ -0,0 +1,14 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// Directory where the launcher keeps its own state (runtimes, logs, caches).
+/// Falls back to the working directory when the platform has no data directory.
+pub fn data_dir() -> PathBuf {
+    let dir = dirs::data_dir()
+        .map(|dir| dir.join("mindlaunch"))
+        .unwrap_or_else(|| PathBuf::from("."));
+    if let Err(e) = fs::create_dir_all(&dir) {
+        println!("cannot create data directory {:?}: {}", dir, e);
+    }
+    dir
+}
@@ -0,0 +1,206 @@
+use super::download;
+use super::installed;
+use super::java::JavaRuntime;
+use super::paths;
+use crate::models::profile::LaunchProfile;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Error, ErrorKind, LineWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How many lines the console keeps in memory, the log file has everything
+const MAX_LINES: usize = 5000;
+
+#[derive(Clone)]
+pub struct LogLine {
+    pub text: String,
+    pub stderr: bool,
+}
+
+pub struct Session {
+    pub tag_name: String,
+    /// The jar the game runs from, its play time is recorded when the game exits
+    pub jar: PathBuf,
+    pub pid: u32,
+    pub profile: LaunchProfile,
+    pub java: Option<JavaRuntime>,
+    /// Mindustry data directory used by this session
+    pub data_dir: PathBuf,
+    pub started: Instant,
+    pub started_at: SystemTime,
+    pub finished: Option<Instant>,
+    pub exit_code: Option<i32>,
+    pub lines: VecDeque<LogLine>,
+    pub log_path: PathBuf,
+}
+
+impl Session {
+    pub fn running(&self) -> bool {
+        self.finished.is_none()
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.finished.unwrap_or_else(Instant::now) - self.started
+    }
+}
+
+/// Handle of a started game, cheap to clone between the UI and background threads
+#[derive(Clone)]
+pub struct GameProcess {
+    pub session: Arc<Mutex<Session>>,
+    child: Arc<Mutex<Child>>,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
+}
+
+pub fn logs_dir() -> PathBuf {
+    paths::data_dir().join("logs")
+}
+
+fn pipe_lines<R: Read + Send + 'static>(
+    reader: R,
+    stderr: bool,
+    session: Arc<Mutex<Session>>,
+    log: Arc<Mutex<LineWriter<File>>>,
+) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            // the pipe is drained until the game closes it, a full pipe would block the game
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => break,
+                Ok(_) => (),
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    println!("cannot read game output: {}", e);
+                    break;
+                }
+            }
+            // not every mod writes UTF-8
+            let text = String::from_utf8_lossy(&buffer)
+                .trim_end_matches(&['\n', '\r'][..])
+                .to_string();
+            if let Err(e) = writeln!(log.lock(), "{}", text) {
+                println!("cannot write game log: {}", e);
+            }
+            let mut session = session.lock();
+            if session.lines.len() >= MAX_LINES {
+                session.lines.pop_front();
+            }
+            session.lines.push_back(LogLine { text, stderr });
+        }
+    });
+}
+
+impl GameProcess {
+    /// Spawns the command with captured output. Every session is logged to `logs/<tag>-<time>.log`.
+    pub fn spawn(
+        mut command: Command,
+        tag_name: &str,
+        jar: &Path,
+        profile: &LaunchProfile,
+        java: Option<&JavaRuntime>,
+        data_dir: PathBuf,
+    ) -> Result<Self, Error> {
+        fs::create_dir_all(logs_dir())?;
+        let log_path = logs_dir().join(format!(
+            "{}-{}.log",
+            download::sanitize_file_name(tag_name),
+            chrono::Local::now().format("%Y-%m-%d_%H-%M-%S")
+        ));
+        let log = Arc::new(Mutex::new(LineWriter::new(File::create(&log_path)?)));
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let session = Arc::new(Mutex::new(Session {
+            tag_name: tag_name.to_string(),
+            jar: jar.to_path_buf(),
+            pid: child.id(),
+            profile: profile.clone(),
+            java: java.cloned(),
+            data_dir,
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            finished: None,
+            exit_code: None,
+            lines: VecDeque::new(),
+            log_path,
+        }));
+
+        if let Some(stdout) = child.stdout.take() {
+            pipe_lines(stdout, false, session.clone(), log.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            pipe_lines(stderr, true, session.clone(), log);
+        }
+
+        let stdin = child.stdin.take();
+        let process = GameProcess {
+            session,
+            child: Arc::new(Mutex::new(child)),
+            stdin: Arc::new(Mutex::new(stdin)),
+        };
+        process.watch();
+        Ok(process)
+    }
+
+    /// Polls the child until it exits so the UI can show the exit code, then adds the play time
+    fn watch(&self) {
+        let process = self.clone();
+        thread::spawn(move || {
+            loop {
+                let status = process.child.lock().try_wait();
+                match status {
+                    Ok(Some(status)) => {
+                        let mut session = process.session.lock();
+                        session.exit_code = status.code();
+                        session.finished = Some(Instant::now());
+                        println!("game process {} exited: {}", session.pid, status);
+                        break;
+                    }
+                    Ok(None) => thread::sleep(Duration::from_millis(200)),
+                    Err(e) => {
+                        println!("cannot wait for game process: {}", e);
+                        process.session.lock().finished = Some(Instant::now());
+                        break;
+                    }
+                }
+            }
+            let (jar, played) = {
+                let session = process.session.lock();
+                (session.jar.clone(), session.uptime())
+            };
+            if let Err(e) = installed::add_play_time(&jar, played) {
+                println!("cannot record play time of {}: {}", jar.display(), e);
+            }
+        });
+    }
+
+    pub fn kill(&self) -> Result<(), Error> {
+        self.child.lock().kill()
+    }
+
+    /// Writes a line to the process input, used for server commands
+    pub fn send_line(&self, line: &str) -> Result<(), Error> {
+        match self.stdin.lock().as_mut() {
+            Some(stdin) => {
+                writeln!(stdin, "{}", line)?;
+                stdin.flush()
+            }
+            None => Err(Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "process input is closed",
+            )),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::models::profile::{Channel, LaunchProfile, ProfilesFile};
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+/// Read from the working directory again before every launch
+pub const PROFILES_FILE: &str = "Profiles.toml";
+
+pub fn load() -> Result<Vec<LaunchProfile>, Error> {
+    let data = match fs::read_to_string(PROFILES_FILE) {
+        Ok(data) => data,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let file: ProfilesFile =
+        toml::from_str(&data).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    Ok(file.profile)
+}
+
+/// Picks the profile for a release: bound by `tag_name` first, then by channel,
+/// then the first profile without any binding. Falls back to an empty profile.
+pub fn resolve(profiles: &[LaunchProfile], tag_name: &str, channel: Channel) -> LaunchProfile {
+    profiles
+        .iter()
+        .find(|profile| profile.tag_name.as_deref() == Some(tag_name))
+        .or_else(|| {
+            profiles
+                .iter()
+                .find(|profile| profile.tag_name.is_none() && profile.channel == Some(channel))
+        })
+        .or_else(|| {
+            profiles
+                .iter()
+                .find(|profile| profile.tag_name.is_none() && profile.channel.is_none())
+        })
+        .cloned()
+        .unwrap_or_else(|| LaunchProfile {
+            name: "default".to_string(),
+            ..LaunchProfile::default()
+        })
+}
+
+/// JVM arguments placed before `-jar`
+pub fn jvm_args(profile: &LaunchProfile) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(max_heap) = profile.max_heap.as_ref().filter(|heap| !heap.is_empty()) {
+        args.push(format!("-Xmx{}", max_heap));
+    }
+    args.extend(profile.jvm_args.iter().cloned());
+    for (key, value) in profile.properties.iter() {
+        args.push(format!("-D{}={}", key, value));
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, tag_name: Option<&str>, channel: Option<Channel>) -> LaunchProfile {
+        LaunchProfile {
+            name: name.to_string(),
+            tag_name: tag_name.map(str::to_string),
+            channel,
+            ..LaunchProfile::default()
+        }
+    }
+
+    #[test]
+    fn resolves_tag_then_channel_then_unbound() {
+        let profiles = vec![
+            profile("unbound", None, None),
+            profile("be", None, Some(Channel::BleedingEdge)),
+            profile("v126", Some("v126"), Some(Channel::Stable)),
+        ];
+        assert_eq!(resolve(&profiles, "v126", Channel::Stable).name, "v126");
+        assert_eq!(
+            resolve(&profiles, "v126", Channel::BleedingEdge).name,
+            "v126"
+        );
+        assert_eq!(
+            resolve(&profiles, "25001", Channel::BleedingEdge).name,
+            "be"
+        );
+        assert_eq!(resolve(&profiles, "v125", Channel::Stable).name, "unbound");
+
+        // a profile bound to another tag never counts for its channel
+        let profiles = vec![profile("v126", Some("v126"), Some(Channel::Stable))];
+        assert_eq!(
+            resolve(&profiles, "v125", Channel::Stable),
+            profile("default", None, None)
+        );
+    }
+
+    #[test]
+    fn builds_jvm_args() {
+        let mut profile = profile("test", None, None);
+        assert!(jvm_args(&profile).is_empty());
+        profile.max_heap = Some(String::new());
+        assert!(jvm_args(&profile).is_empty());
+
+        profile.max_heap = Some("2G".to_string());
+        profile.jvm_args = vec!["-XX:+UseG1GC".to_string()];
+        profile
+            .properties
+            .insert("file.encoding".to_string(), "UTF-8".to_string());
+        profile
+            .properties
+            .insert("a".to_string(), "b c".to_string());
+        assert_eq!(
+            jvm_args(&profile),
+            vec!["-Xmx2G", "-XX:+UseG1GC", "-Da=b c", "-Dfile.encoding=UTF-8"]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use super::ReleaseProvider;
+use crate::ml_core::releases::{self, Paging};
+use crate::models::release_cache::ReleaseCache;
+use crate::models::source::ReleaseSource;
+use crate::models::version::{Release, ReleaseAsset};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// A local directory, every jar in it is one release named after the file
+pub struct DirectoryProvider {
+    source: ReleaseSource,
+    path: PathBuf,
+}
+
+impl DirectoryProvider {
+    pub fn new(source: &ReleaseSource) -> Result<Self, Error> {
+        let path = source
+            .path
+            .as_ref()
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("source {} needs path", source.name),
+                )
+            })?;
+        Ok(DirectoryProvider {
+            source: source.clone(),
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+impl ReleaseProvider for DirectoryProvider {
+    /// Newest jars first, like the API lists
+    fn fetch(
+        &self,
+        _cache: &mut ReleaseCache,
+        _paging: Paging,
+        on_page: &mut dyn FnMut(Vec<Release>) -> bool,
+    ) -> Result<(), Error> {
+        let mut jars = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "jar") {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            jars.push((modified, path.canonicalize()?, metadata.len()));
+        }
+        jars.sort_by_key(|jar| std::cmp::Reverse(jar.0));
+
+        on_page(
+            jars.into_iter()
+                .filter_map(|(modified, path, size)| {
+                    let name = path.file_name()?.to_str()?.to_string();
+                    let tag_name = path.file_stem()?.to_str()?.to_string();
+                    // a `<name>.jar.sha256` next to the jar, as `sha256sum` writes it
+                    let sha256 =
+                        fs::read_to_string(path.with_file_name(format!("{}.sha256", name)))
+                            .ok()
+                            .and_then(|data| Some(data.split_whitespace().next()?.to_lowercase()));
+                    let asset = ReleaseAsset {
+                        name: name.clone(),
+                        size,
+                        download_url: format!("file://{}", path.display()),
+                        sha256,
+                    };
+                    Some(Release {
+                        published_at: Some(DateTime::<Utc>::from(modified)),
+                        ..releases::build(Some(name), Some(tag_name), vec![asset], &self.source)
+                    })
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::temp_dir;
+    use crate::models::source::SourceKind;
+    use std::time::Duration;
+
+    fn jar(path: &std::path::Path, size: usize, age: u64) {
+        fs::write(path, vec![0; size]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn lists_the_jars_newest_first() {
+        let dir = temp_dir("directory-provider");
+        jar(&dir.join("v126.jar"), 10, 3600);
+        jar(&dir.join("v146.jar"), 20, 60);
+        fs::write(dir.join("notes.txt"), "not a jar").unwrap();
+        fs::create_dir(dir.join("folder.jar")).unwrap();
+        fs::write(dir.join("v146.jar.sha256"), "ABCDEF  v146.jar\n").unwrap();
+        let source = ReleaseSource {
+            name: "Local".to_string(),
+            kind: SourceKind::Directory,
+            path: Some(dir.to_string_lossy().to_string()),
+            ..ReleaseSource::default()
+        };
+
+        let mut releases = Vec::new();
+        DirectoryProvider::new(&source)
+            .unwrap()
+            .fetch(
+                &mut ReleaseCache::default(),
+                Paging::default(),
+                &mut |page| {
+                    releases.extend(page);
+                    true
+                },
+            )
+            .unwrap();
+
+        let tags: Vec<&str> = releases
+            .iter()
+            .map(|release| release.tag_name.as_str())
+            .collect();
+        assert_eq!(tags, vec!["v146", "v126"]);
+        let desktop = releases[0].desktop.as_ref().unwrap();
+        assert_eq!(desktop.size, 20);
+        assert_eq!(desktop.sha256.as_deref(), Some("abcdef"));
+        assert!(desktop.download_url.starts_with("file://"));
+        assert_eq!(releases[1].desktop.as_ref().unwrap().sha256, None);
+        assert!(releases[0].published_at > releases[1].published_at);
+    }
+
+    #[test]
+    fn needs_a_path() {
+        let source = ReleaseSource {
+            kind: SourceKind::Directory,
+            ..ReleaseSource::default()
+        };
+        assert!(DirectoryProvider::new(&source).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use super::ReleaseProvider;
+use crate::ml_core::releases::{self, Paging};
+use crate::ml_core::{http_tools, sources};
+use crate::models::gitea::GiteaRelease;
+use crate::models::release_cache::ReleaseCache;
+use crate::models::source::ReleaseSource;
+use crate::models::version::{Release, ReleaseAsset};
+use std::io::{Error, ErrorKind};
+
+/// Gitea and Forgejo, `api_url` is `https://<host>/api/v1/repos/<owner>/<repo>/releases`
+pub struct GiteaProvider {
+    source: ReleaseSource,
+    url: String,
+}
+
+impl GiteaProvider {
+    pub fn new(source: &ReleaseSource) -> Result<Self, Error> {
+        Ok(GiteaProvider {
+            source: source.clone(),
+            url: sources::releases_url(source)?,
+        })
+    }
+
+    fn convert(&self, release: &GiteaRelease) -> Release {
+        let assets = release
+            .assets
+            .iter()
+            .map(|asset| ReleaseAsset {
+                name: asset.name.clone(),
+                size: asset.size.max(0) as u64,
+                download_url: asset.browser_download_url.clone(),
+                sha256: None,
+            })
+            .collect();
+        Release {
+            body: release.body.clone(),
+            html_url: release.html_url.clone(),
+            prerelease: release.prerelease,
+            published_at: releases::parse_date(release.published_at.as_deref()),
+            ..releases::build(
+                release.name.clone().filter(|name| !name.is_empty()),
+                release.tag_name.clone(),
+                assets,
+                &self.source,
+            )
+        }
+    }
+}
+
+impl ReleaseProvider for GiteaProvider {
+    /// Gitea pages with `limit` and sends the same `Link` headers as GitHub
+    fn fetch(
+        &self,
+        _cache: &mut ReleaseCache,
+        paging: Paging,
+        on_page: &mut dyn FnMut(Vec<Release>) -> bool,
+    ) -> Result<(), Error> {
+        let mut next = Some(releases::with_param(&self.url, "limit", paging.per_page));
+        let mut pages = 0;
+        while let Some(page_url) = next.take() {
+            if pages >= paging.max_pages {
+                break;
+            }
+            let response = http_tools::get(&page_url, &[])?;
+            next = response.header("link").and_then(releases::next_link);
+            let data = response.into_string()?;
+            let page: Vec<GiteaRelease> =
+                serde_json::from_str(&data).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            pages += 1;
+            let page = page
+                .iter()
+                .filter(|release| !release.draft)
+                .map(|release| self.convert(release))
+                .collect();
+            if !on_page(page) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::{Response, TestServer};
+    use crate::models::profile::Channel;
+    use crate::models::source::SourceKind;
+
+    /// Two pages as a Forgejo instance serves them, the second one has a draft
+    fn gitea_server() -> TestServer {
+        TestServer::start(|request| {
+            let host = request.header("host").unwrap_or("").to_string();
+            if request.path.ends_with("page=2") {
+                return Response::ok(
+                    r#"[
+                    {"id": 2, "tag_name": "v2-draft", "name": "", "draft": true, "assets": []},
+                    {"id": 1, "tag_name": "v1", "name": "", "prerelease": true,
+                     "published_at": "2022-01-02T03:04:05Z",
+                     "assets": [{"id": 10, "name": "Mindustry.jar", "size": 42,
+                                 "browser_download_url": "https://git.example.org/a/v1/Mindustry.jar"}]}
+                ]"#,
+                );
+            }
+            Response::ok(
+                r#"[{"id": 3, "tag_name": "v3", "name": "Third", "body": "notes",
+                     "html_url": "https://git.example.org/r/v3",
+                     "assets": [{"id": 11, "name": "server-release.jar", "size": 7,
+                                 "browser_download_url": "https://git.example.org/a/v3/server-release.jar"},
+                                {"id": 12, "name": "Mindustry.jar", "size": 8,
+                                 "browser_download_url": "https://git.example.org/a/v3/Mindustry.jar"}]}]"#,
+            )
+            .header(
+                "Link",
+                &format!("<http://{}/releases?limit=50&page=2>; rel=\"next\"", host),
+            )
+        })
+    }
+
+    #[test]
+    fn fetches_every_page_without_drafts() {
+        let server = gitea_server();
+        let source = ReleaseSource {
+            name: "Forgejo".to_string(),
+            kind: SourceKind::Gitea,
+            channel: Channel::Custom,
+            api_url: Some(server.url("/releases")),
+            ..ReleaseSource::default()
+        };
+        let provider = GiteaProvider::new(&source).unwrap();
+        let paging = Paging {
+            per_page: 50,
+            max_pages: 10,
+        };
+        let mut releases = Vec::new();
+        provider
+            .fetch(&mut ReleaseCache::default(), paging, &mut |page| {
+                releases.extend(page);
+                true
+            })
+            .unwrap();
+
+        let tags: Vec<&str> = releases
+            .iter()
+            .map(|release| release.tag_name.as_str())
+            .collect();
+        assert_eq!(tags, vec!["v3", "v1"]);
+        assert_eq!(releases[0].name, "Third");
+        assert_eq!(releases[0].desktop.as_ref().unwrap().size, 8);
+        assert_eq!(releases[0].server.as_ref().unwrap().size, 7);
+        assert_eq!(releases[0].body.as_deref(), Some("notes"));
+        // an empty name falls back to the source name of custom sources
+        assert_eq!(releases[1].name, "Forgejo");
+        assert!(releases[1].prerelease);
+        assert!(releases[1].published_at.is_some());
+        assert_eq!(server.requests()[0].path, "/releases?limit=50");
+    }
+}
@@ -0,0 +1,308 @@
+use super::ReleaseProvider;
+use crate::ml_core::releases::{self, Paging};
+use crate::ml_core::{http_tools, paths, sources};
+use crate::models::release::Root;
+use crate::models::release_cache::{CachedPage, ReleaseCache};
+use crate::models::source::ReleaseSource;
+use crate::models::version::{Release, ReleaseAsset};
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+const CACHE_FILE: &str = "releases.json";
+
+impl ReleaseCache {
+    /// A missing or broken cache is the same as an empty one
+    pub fn load() -> Self {
+        fs::read_to_string(paths::data_dir().join(CACHE_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Not synced to disk, `load` takes a cache broken by a crash as an empty one
+    pub fn save(&self) -> Result<(), Error> {
+        let data = serde_json::to_string(self)?;
+        let path = paths::data_dir().join(CACHE_FILE);
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, data)?;
+        fs::rename(&temp, &path)
+    }
+}
+
+/// Converts a GitHub release, picking the desktop and server jars by the asset rules of the source
+pub fn from_github(root: &Root, source: &ReleaseSource) -> Release {
+    let assets = root
+        .assets
+        .iter()
+        .filter_map(|asset| {
+            Some(ReleaseAsset {
+                name: asset.name.clone()?,
+                size: asset.size.max(0) as u64,
+                download_url: asset.browser_download_url.clone()?,
+                sha256: asset
+                    .digest
+                    .as_deref()
+                    .and_then(|digest| digest.strip_prefix("sha256:"))
+                    .map(|hex| hex.to_ascii_lowercase()),
+            })
+        })
+        .collect();
+    Release {
+        body: root.body.clone(),
+        html_url: root.html_url.clone(),
+        prerelease: root.prerelease,
+        published_at: releases::parse_date(root.published_at.as_deref()),
+        ..releases::build(root.name.clone(), root.tag_name.clone(), assets, source)
+    }
+}
+
+/// Fetches one page. A cached page is revalidated with `If-None-Match` and reused on `304 Not Modified`.
+fn fetch_page(url: &str, cached: Option<&CachedPage>) -> Result<CachedPage, Error> {
+    let mut headers = Vec::new();
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            headers.push(("If-None-Match", etag.as_str()));
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            headers.push(("If-Modified-Since", last_modified.as_str()));
+        }
+    }
+    let response = http_tools::get(url, &headers)?;
+    if response.status() == 304 {
+        if let Some(cached) = cached {
+            return Ok(cached.clone());
+        }
+    }
+    let etag = response.header("etag").map(String::from);
+    let last_modified = response.header("last-modified").map(String::from);
+    let next = response.header("link").and_then(releases::next_link);
+    let data = response.into_string()?;
+    let releases: Vec<Root> =
+        serde_json::from_str(&data).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    Ok(CachedPage {
+        etag,
+        last_modified,
+        next,
+        releases,
+    })
+}
+
+/// The GitHub releases API, pages are kept in the release cache
+pub struct GithubProvider {
+    source: ReleaseSource,
+    url: String,
+}
+
+impl GithubProvider {
+    pub fn new(source: &ReleaseSource) -> Result<Self, Error> {
+        Ok(GithubProvider {
+            source: source.clone(),
+            url: sources::releases_url(source)?,
+        })
+    }
+
+    fn first_page(&self, paging: Paging) -> String {
+        releases::with_param(&self.url, "per_page", paging.per_page)
+    }
+}
+
+impl ReleaseProvider for GithubProvider {
+    /// Releases as far as the cache has them
+    fn cached(&self, cache: &ReleaseCache, paging: Paging) -> Vec<Release> {
+        let mut releases = Vec::new();
+        let mut next = Some(self.first_page(paging));
+        let mut pages = 0;
+        while let Some(page) = next.and_then(|page_url| cache.pages.get(&page_url)) {
+            if pages >= paging.max_pages {
+                break;
+            }
+            releases.extend(
+                page.releases
+                    .iter()
+                    .map(|root| from_github(root, &self.source)),
+            );
+            next = page.next.clone();
+            pages += 1;
+        }
+        releases
+    }
+
+    /// Follows the `Link` headers page by page and stores every page in `cache`
+    fn fetch(
+        &self,
+        cache: &mut ReleaseCache,
+        paging: Paging,
+        on_page: &mut dyn FnMut(Vec<Release>) -> bool,
+    ) -> Result<(), Error> {
+        let mut next = Some(self.first_page(paging));
+        let mut pages = 0;
+        while let Some(page_url) = next.take() {
+            if pages >= paging.max_pages {
+                break;
+            }
+            let page = fetch_page(&page_url, cache.pages.get(&page_url))?;
+            let releases = page
+                .releases
+                .iter()
+                .map(|root| from_github(root, &self.source))
+                .collect();
+            next = page.next.clone();
+            cache.pages.insert(page_url, page);
+            pages += 1;
+            if !on_page(releases) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::{Response, TestServer};
+    use crate::models::source::SourceKind;
+
+    /// A release as the API returns it, trimmed to the fields the launcher reads or requires
+    fn release_json(tag_name: &str, id: u32) -> String {
+        format!(
+            r#"{{"id": {id}, "tag_name": "{tag}", "name": "Build {id}", "draft": false, "prerelease": false,
+            "author": {{"login": "Anuken", "id": 9, "site_admin": false}},
+            "published_at": "2021-03-0{day}T12:00:00Z", "body": "notes", "html_url": "https://github.com/r/{tag}",
+            "assets": [
+                {{"id": 1, "name": "Mindustry.jar", "size": 100, "browser_download_url": "https://github.com/d/{tag}/Mindustry.jar",
+                  "uploader": {{"id": 9, "site_admin": false}}, "download_count": 1,
+                  "digest": "sha256:ABCDEF"}},
+                {{"id": 2, "name": "server-release.jar", "size": 50, "browser_download_url": "https://github.com/d/{tag}/server-release.jar",
+                  "uploader": {{"id": 9, "site_admin": false}}, "download_count": 1}}
+            ]}}"#,
+            id = id,
+            tag = tag_name,
+            day = id % 9 + 1
+        )
+    }
+
+    fn source(api_url: &str) -> ReleaseSource {
+        ReleaseSource {
+            name: "Test".to_string(),
+            kind: SourceKind::Github,
+            api_url: Some(api_url.to_string()),
+            ..ReleaseSource::default()
+        }
+    }
+
+    #[test]
+    fn parses_a_release() {
+        let root: Root = serde_json::from_str(&release_json("v126.2", 7)).unwrap();
+        let release = from_github(&root, &source("https://example.org/releases"));
+        assert_eq!(release.tag_name, "v126.2");
+        assert_eq!(release.name, "Build 7");
+        assert_eq!(release.assets.len(), 2);
+        let desktop = release.desktop.unwrap();
+        assert_eq!(desktop.name, "Mindustry.jar");
+        assert_eq!(desktop.size, 100);
+        assert_eq!(desktop.sha256.as_deref(), Some("abcdef"));
+        assert_eq!(release.server.unwrap().name, "server-release.jar");
+        assert_eq!(release.body.as_deref(), Some("notes"));
+        assert!(release.published_at.is_some());
+        assert_eq!(release.source, "Test");
+    }
+
+    #[test]
+    fn revalidates_cached_pages() {
+        let server = TestServer::start(|request| match request.header("if-none-match") {
+            Some("\"v1\"") => Response::new(304, ""),
+            _ => Response::ok(format!("[{}]", release_json("v1", 1))).header("ETag", "\"v1\""),
+        });
+        let provider = GithubProvider::new(&source(&server.url("/releases"))).unwrap();
+        let mut cache = ReleaseCache::default();
+        for _ in 0..2 {
+            let mut tags = Vec::new();
+            provider
+                .fetch(&mut cache, Paging::default(), &mut |page| {
+                    tags.extend(page.into_iter().map(|release| release.tag_name));
+                    true
+                })
+                .unwrap();
+            assert_eq!(tags, vec!["v1"]);
+        }
+        let requests = server.requests();
+        assert_eq!(requests[0].header("if-none-match"), None);
+        assert_eq!(requests[1].header("if-none-match"), Some("\"v1\""));
+    }
+
+    /// Every page links to the next one, two releases per page without end
+    fn endless_pages() -> TestServer {
+        TestServer::start(|request| {
+            let page: u32 = request
+                .path
+                .split("page=")
+                .nth(2)
+                .and_then(|page| page.parse().ok())
+                .unwrap_or(1);
+            let body = format!(
+                "[{}, {}]",
+                release_json(&format!("v{}", page * 2), page * 2),
+                release_json(&format!("v{}", page * 2 + 1), page * 2 + 1)
+            );
+            let host = request.header("host").unwrap_or("").to_string();
+            Response::ok(body).header(
+                "Link",
+                &format!(
+                    "<http://{}/releases?per_page=2&page={}>; rel=\"next\"",
+                    host,
+                    page + 1
+                ),
+            )
+        })
+    }
+
+    #[test]
+    fn stops_at_the_page_cap() {
+        let server = endless_pages();
+        let provider = GithubProvider::new(&source(&server.url("/releases"))).unwrap();
+        let paging = Paging {
+            per_page: 2,
+            max_pages: 3,
+        };
+        let mut cache = ReleaseCache::default();
+        let mut tags = Vec::new();
+        provider
+            .fetch(&mut cache, paging, &mut |page| {
+                tags.extend(page.into_iter().map(|release| release.tag_name));
+                true
+            })
+            .unwrap();
+        assert_eq!(tags, vec!["v2", "v3", "v4", "v5", "v6", "v7"]);
+        let paths: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/releases?per_page=2",
+                "/releases?per_page=2&page=2",
+                "/releases?per_page=2&page=3"
+            ]
+        );
+        // the cached pages follow the same links and the same cap
+        assert_eq!(provider.cached(&cache, paging).len(), 6);
+    }
+
+    #[test]
+    fn stops_when_the_caller_has_enough() {
+        let server = endless_pages();
+        let provider = GithubProvider::new(&source(&server.url("/releases"))).unwrap();
+        let mut pages = 0;
+        provider
+            .fetch(&mut ReleaseCache::default(), Paging::default(), &mut |_| {
+                pages += 1;
+                false
+            })
+            .unwrap();
+        assert_eq!(pages, 1);
+        assert_eq!(server.requests().len(), 1);
+    }
+}
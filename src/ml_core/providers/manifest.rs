@@ -0,0 +1,132 @@
+use super::ReleaseProvider;
+use crate::ml_core::releases::{self, Paging};
+use crate::ml_core::{http_tools, sources};
+use crate::models::manifest::Manifest;
+use crate::models::release_cache::ReleaseCache;
+use crate::models::source::ReleaseSource;
+use crate::models::version::Release;
+use std::io::{Error, ErrorKind};
+
+/// A static JSON manifest on any HTTP server, `api_url` points to the file
+pub struct ManifestProvider {
+    source: ReleaseSource,
+    url: String,
+}
+
+impl ManifestProvider {
+    pub fn new(source: &ReleaseSource) -> Result<Self, Error> {
+        Ok(ManifestProvider {
+            source: source.clone(),
+            url: sources::releases_url(source)?,
+        })
+    }
+}
+
+impl ReleaseProvider for ManifestProvider {
+    /// The whole manifest is one batch, paging does not apply
+    fn fetch(
+        &self,
+        _cache: &mut ReleaseCache,
+        _paging: Paging,
+        on_page: &mut dyn FnMut(Vec<Release>) -> bool,
+    ) -> Result<(), Error> {
+        let data = http_tools::get(&self.url, &[])?.into_string()?;
+        let manifest: Manifest =
+            serde_json::from_str(&data).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        on_page(
+            manifest
+                .releases
+                .into_iter()
+                .map(|release| Release {
+                    body: release.body,
+                    html_url: release.html_url,
+                    prerelease: release.prerelease,
+                    published_at: releases::parse_date(release.published_at.as_deref()),
+                    ..releases::build(
+                        release.name,
+                        Some(release.tag_name),
+                        release.assets,
+                        &self.source,
+                    )
+                })
+                .collect(),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::{Response, TestServer};
+    use crate::models::source::SourceKind;
+
+    const MANIFEST: &str = r#"{ "releases": [
+        { "tag_name": "v146", "name": "Build 146", "prerelease": false,
+          "published_at": "2024-09-01T00:00:00Z", "body": "notes",
+          "assets": [
+            { "name": "Mindustry.jar", "size": 123, "download_url": "https://example.org/146/Mindustry.jar",
+              "sha256": "ab12" },
+            { "name": "server-release.jar", "size": 45, "download_url": "https://example.org/146/server-release.jar" }
+          ] },
+        { "tag_name": "v145-rc1", "prerelease": true }
+    ] }"#;
+
+    #[test]
+    fn parses_the_manifest() {
+        let manifest: Manifest = serde_json::from_str(MANIFEST).unwrap();
+        assert_eq!(manifest.releases.len(), 2);
+        assert_eq!(
+            manifest.releases[0].assets[0].sha256.as_deref(),
+            Some("ab12")
+        );
+        assert!(manifest.releases[1].assets.is_empty());
+        assert_eq!(manifest.releases[1].name, None);
+    }
+
+    #[test]
+    fn fetches_the_manifest() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/manifest.json" => Response::ok(MANIFEST),
+            _ => Response::new(404, ""),
+        });
+        let source = ReleaseSource {
+            name: "Mirror".to_string(),
+            kind: SourceKind::Manifest,
+            api_url: Some(server.url("/manifest.json")),
+            ..ReleaseSource::default()
+        };
+        let mut releases = Vec::new();
+        ManifestProvider::new(&source)
+            .unwrap()
+            .fetch(
+                &mut ReleaseCache::default(),
+                Paging::default(),
+                &mut |page| {
+                    releases.extend(page);
+                    true
+                },
+            )
+            .unwrap();
+
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].name, "Build 146");
+        assert_eq!(releases[0].desktop.as_ref().unwrap().size, 123);
+        assert_eq!(releases[0].server.as_ref().unwrap().size, 45);
+        assert!(releases[0].published_at.is_some());
+        assert!(releases[1].prerelease);
+        assert!(releases[1].desktop.is_none());
+        assert_eq!(releases[1].source, "Mirror");
+
+        let missing = ReleaseSource {
+            api_url: Some(server.url("/missing.json")),
+            ..source
+        };
+        assert!(ManifestProvider::new(&missing)
+            .unwrap()
+            .fetch(&mut ReleaseCache::default(), Paging::default(), &mut |_| {
+                true
+            })
+            .is_err());
+    }
+}
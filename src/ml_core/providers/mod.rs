@@ -0,0 +1,36 @@
+use super::releases::Paging;
+use crate::models::release_cache::ReleaseCache;
+use crate::models::source::{ReleaseSource, SourceKind};
+use crate::models::version::Release;
+use std::io::Error;
+
+pub mod directory;
+pub mod gitea;
+pub mod github;
+pub mod manifest;
+
+/// A backend that lists releases. Every backend produces the same `Release` records.
+pub trait ReleaseProvider {
+    /// Releases known without the network, shown while `fetch` runs
+    fn cached(&self, _cache: &ReleaseCache, _paging: Paging) -> Vec<Release> {
+        Vec::new()
+    }
+
+    /// Loads the releases batch by batch. `on_page` gets every batch and returns `false` to stop.
+    fn fetch(
+        &self,
+        cache: &mut ReleaseCache,
+        paging: Paging,
+        on_page: &mut dyn FnMut(Vec<Release>) -> bool,
+    ) -> Result<(), Error>;
+}
+
+/// The backend for `source.kind`, fails when the source misses its repo, URL or path
+pub fn for_source(source: &ReleaseSource) -> Result<Box<dyn ReleaseProvider>, Error> {
+    Ok(match source.kind {
+        SourceKind::Github => Box::new(github::GithubProvider::new(source)?),
+        SourceKind::Gitea => Box::new(gitea::GiteaProvider::new(source)?),
+        SourceKind::Manifest => Box::new(manifest::ManifestProvider::new(source)?),
+        SourceKind::Directory => Box::new(directory::DirectoryProvider::new(source)?),
+    })
+}
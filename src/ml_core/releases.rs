@@ -0,0 +1,311 @@
+use crate::models::game_version::GameVersion;
+use crate::models::profile::Channel;
+use crate::models::source::ReleaseSource;
+use crate::models::version::{Release, ReleaseAsset};
+use chrono::{DateTime, Utc};
+
+/// Matches `name` against a pattern where `*` stands for any run of characters, ignoring case
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.to_lowercase(), name.to_lowercase());
+    let (pattern, name) = (pattern.as_str(), name.as_str());
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if name.len() < first.len() + last.len() || !name.starts_with(first) || !name.ends_with(last) {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// First asset matching the patterns in their order, skipping the excluded ones
+fn pick<'a>(
+    assets: &'a [ReleaseAsset],
+    patterns: &[String],
+    exclude: &[String],
+) -> Option<&'a ReleaseAsset> {
+    patterns.iter().find_map(|pattern| {
+        assets.iter().find(|asset| {
+            glob_match(pattern, &asset.name)
+                && !exclude
+                    .iter()
+                    .any(|excluded| glob_match(excluded, &asset.name))
+        })
+    })
+}
+
+/// Builds a release from its assets, picking the desktop and server jars by the asset rules of the source
+pub fn build(
+    name: Option<String>,
+    tag_name: Option<String>,
+    assets: Vec<ReleaseAsset>,
+    source: &ReleaseSource,
+) -> Release {
+    let rules = &source.assets;
+    // a `*.jar` fallback for the desktop jar must not pick the server
+    let desktop = pick(&assets, &rules.desktop, &rules.server).cloned();
+    let server = pick(&assets, &rules.server, &[]).cloned();
+    let default_name = match source.channel {
+        Channel::Stable => "Unknown version string",
+        Channel::BleedingEdge => "Bleeding edge build",
+        Channel::Custom => &source.name,
+    };
+    let version = tag_name
+        .as_deref()
+        .and_then(GameVersion::parse)
+        .or_else(|| name.as_deref().and_then(GameVersion::parse));
+    Release {
+        name: name.unwrap_or_else(|| default_name.to_string()),
+        tag_name: tag_name.unwrap_or_else(|| "Unknown".to_string()),
+        version,
+        published_at: None,
+        assets,
+        desktop,
+        server,
+        body: None,
+        html_url: None,
+        prerelease: false,
+        installed: false,
+        jar: None,
+        channel: source.channel,
+        source: source.name.clone(),
+    }
+}
+
+/// Parses an RFC 3339 date like `2023-05-04T12:00:00Z` as the APIs send it
+pub fn parse_date(date: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date?)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Sort key of a release: `(major, minor, build)`. A bleeding-edge build takes the version of the newest
+/// release published before it, so it sorts after that release and before the next one.
+/// `newest` holds publish dates in order with the newest version released up to each of them.
+fn version_key(release: &Release, newest: &[(DateTime<Utc>, (u32, u32))]) -> (u32, u32, u32) {
+    match release.version {
+        Some(GameVersion::Release { major, minor }) => (major, minor, 0),
+        Some(GameVersion::Build(build)) => {
+            let base = release.published_at.and_then(|published| {
+                let released = newest.partition_point(|(date, _)| *date <= published);
+                released.checked_sub(1).map(|index| newest[index].1)
+            });
+            let (major, minor) = base.unwrap_or((0, 0));
+            (major, minor, build)
+        }
+        None => (0, 0, 0),
+    }
+}
+
+/// Sorts oldest to newest by parsed version, `release` gives the release of an item.
+/// Releases and bleeding-edge builds are compared through their publish dates,
+/// equal versions by publish date and tag.
+pub fn sort_by_version<'a, T>(items: &mut [T], release: impl Fn(&T) -> &'a Release) {
+    let mut numbered: Vec<(DateTime<Utc>, (u32, u32))> = items
+        .iter()
+        .map(&release)
+        .filter_map(|release| match (release.version, release.published_at) {
+            (Some(GameVersion::Release { major, minor }), Some(published)) => {
+                Some((published, (major, minor)))
+            }
+            _ => None,
+        })
+        .collect();
+    numbered.sort();
+    // a patch of an old line can come out after a newer release, keep the newest so far
+    let mut newest = (0, 0);
+    for (_, version) in numbered.iter_mut() {
+        newest = newest.max(*version);
+        *version = newest;
+    }
+    items.sort_by_cached_key(|item| {
+        let release = release(item);
+        (
+            version_key(release, &numbered),
+            release.published_at,
+            release.tag_name.clone(),
+        )
+    });
+}
+
+/// Position of the newest release of a channel by version, not by list position
+pub fn latest_index(releases: &[Release], channel: Channel) -> Option<usize> {
+    let mut sorted: Vec<usize> = (0..releases.len())
+        .filter(|&index| releases[index].channel == channel)
+        .collect();
+    sort_by_version(&mut sorted, |&index| &releases[index]);
+    sorted.pop()
+}
+
+/// How much of the release history is requested
+#[derive(Debug, Clone, Copy)]
+pub struct Paging {
+    pub per_page: u32,
+    /// Stop after this many pages, bleeding edge has thousands of builds
+    pub max_pages: u32,
+}
+
+impl Default for Paging {
+    fn default() -> Self {
+        Paging {
+            per_page: 100,
+            max_pages: 10,
+        }
+    }
+}
+
+/// The `rel="next"` URL of a `Link` header: `<url?page=2>; rel="next", <url?page=5>; rel="last"`
+pub fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let url = parts.next()?.trim();
+        if parts.any(|param| param.trim() == "rel=\"next\"") {
+            Some(
+                url.trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string(),
+            )
+        } else {
+            None
+        }
+    })
+}
+
+/// Appends a query parameter, e.g. `per_page=100`
+pub fn with_param(url: &str, name: &str, value: u32) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}{}={}", url, separator, name, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(name: &str) -> ReleaseAsset {
+        ReleaseAsset {
+            name: name.to_string(),
+            ..ReleaseAsset::default()
+        }
+    }
+
+    #[test]
+    fn matches_globs_ignoring_case() {
+        assert!(glob_match("*server*.jar", "Mindustry-BE-Server-25872.jar"));
+        assert!(glob_match("mindustry.jar", "Mindustry.jar"));
+        assert!(glob_match("*.JAR", "game.jar"));
+        assert!(!glob_match(
+            "*desktop*.jar",
+            "Mindustry-BE-Server-25872.jar"
+        ));
+        assert!(!glob_match("server*.jar", "Mindustry.jar"));
+    }
+
+    #[test]
+    fn picks_bleeding_edge_jars_in_any_order() {
+        let source = ReleaseSource::default();
+        let names = [
+            "Mindustry-BE-Server-25872.jar",
+            "Mindustry-BE-Desktop-25872.jar",
+        ];
+        for order in [[0, 1], [1, 0]].iter() {
+            let assets = order.iter().map(|&index| asset(names[index])).collect();
+            let release = build(None, Some("25872".to_string()), assets, &source);
+            assert_eq!(release.desktop.unwrap().name, names[1]);
+            assert_eq!(release.server.unwrap().name, names[0]);
+        }
+
+        let assets = vec![asset("server-release.jar"), asset("Mindustry.jar")];
+        let release = build(None, Some("v146".to_string()), assets, &source);
+        assert_eq!(release.desktop.unwrap().name, "Mindustry.jar");
+        assert_eq!(release.server.unwrap().name, "server-release.jar");
+    }
+
+    #[test]
+    fn follows_the_next_link() {
+        let header =
+            "<https://api.github.com/repositories/1/releases?per_page=100&page=2>; rel=\"next\", \
+            <https://api.github.com/repositories/1/releases?per_page=100&page=7>; rel=\"last\"";
+        assert_eq!(
+            next_link(header).as_deref(),
+            Some("https://api.github.com/repositories/1/releases?per_page=100&page=2")
+        );
+        let last =
+            "<https://api.github.com/repositories/1/releases?per_page=100&page=6>; rel=\"prev\", \
+            <https://api.github.com/repositories/1/releases?per_page=100&page=1>; rel=\"first\"";
+        assert_eq!(next_link(last), None);
+        assert_eq!(next_link(""), None);
+    }
+
+    #[test]
+    fn appends_query_parameters() {
+        assert_eq!(
+            with_param("https://example.org/releases", "per_page", 100),
+            "https://example.org/releases?per_page=100"
+        );
+        assert_eq!(
+            with_param("https://example.org/releases?draft=false", "limit", 50),
+            "https://example.org/releases?draft=false&limit=50"
+        );
+    }
+
+    fn dated(tag_name: &str, channel: Channel, date: Option<&str>) -> Release {
+        Release {
+            name: tag_name.to_string(),
+            tag_name: tag_name.to_string(),
+            version: GameVersion::parse(tag_name),
+            published_at: parse_date(date),
+            channel,
+            ..Release::default()
+        }
+    }
+
+    /// Releases in no particular order with the builds between them
+    fn history() -> Vec<Release> {
+        vec![
+            dated("v146", Channel::Stable, Some("2024-09-01T00:00:00Z")),
+            dated("22000", Channel::BleedingEdge, Some("2021-07-01T00:00:00Z")),
+            dated("v104.6", Channel::Stable, Some("2020-01-01T00:00:00Z")),
+            dated("20000", Channel::BleedingEdge, Some("2020-06-01T00:00:00Z")),
+            dated("custom", Channel::Custom, Some("2022-01-01T00:00:00Z")),
+            dated("v126.2", Channel::Stable, Some("2021-01-01T00:00:00Z")),
+            dated("5", Channel::BleedingEdge, None),
+            // a patch of the old line, published after v126.2
+            dated("v104.10", Channel::Stable, Some("2021-06-01T00:00:00Z")),
+            dated("21000", Channel::BleedingEdge, Some("2021-03-01T00:00:00Z")),
+        ]
+    }
+
+    #[test]
+    fn sorts_builds_between_releases_by_date() {
+        let history = history();
+        let mut sorted: Vec<&Release> = history.iter().collect();
+        sort_by_version(&mut sorted, |release| *release);
+        let tags: Vec<&str> = sorted
+            .iter()
+            .map(|release| release.tag_name.as_str())
+            .collect();
+        assert_eq!(
+            tags,
+            vec!["custom", "5", "v104.6", "20000", "v104.10", "v126.2", "21000", "22000", "v146"]
+        );
+    }
+
+    #[test]
+    fn picks_the_latest_by_version() {
+        let history = history();
+        let tag =
+            |channel| latest_index(&history, channel).map(|index| history[index].tag_name.as_str());
+        assert_eq!(tag(Channel::Stable), Some("v146"));
+        assert_eq!(tag(Channel::BleedingEdge), Some("22000"));
+        assert_eq!(tag(Channel::Custom), Some("custom"));
+        assert_eq!(latest_index(&[], Channel::Stable), None);
+    }
+}
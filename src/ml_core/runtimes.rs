@@ -0,0 +1,544 @@
+use super::http_tools;
+use super::java::{self, JavaRuntime, RuntimeSelection};
+use super::paths;
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Error, ErrorKind, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Directory with JREs downloaded by the launcher
+pub fn runtimes_dir() -> PathBuf {
+    paths::data_dir().join("runtimes")
+}
+
+/// Java binaries of every managed runtime. Archives usually wrap the JRE in one
+/// top-level folder, macOS builds nest it deeper in `Contents/Home`.
+pub fn managed_java_binaries() -> Vec<PathBuf> {
+    let mut binaries = Vec::new();
+    if let Ok(entries) = fs::read_dir(runtimes_dir()) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if let Some(binary) = find_java_binary(&entry.path()) {
+                binaries.push(binary);
+            }
+        }
+    }
+    binaries.sort();
+    binaries
+}
+
+fn find_java_binary(root: &Path) -> Option<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    // root, root/<jre>, root/<jre>/Contents/Home
+    for _ in 0..4 {
+        let mut next = Vec::new();
+        for dir in dirs {
+            let binary = dir.join("bin").join(java::JAVA_BINARY);
+            if binary.is_file() {
+                return Some(binary);
+            }
+            if let Ok(entries) = fs::read_dir(&dir) {
+                next.extend(
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .map(|entry| entry.path())
+                        .filter(|path| path.is_dir()),
+                );
+            }
+        }
+        dirs = next;
+    }
+    None
+}
+
+/// Joins an archive entry path to `dest`, refusing absolute paths and `..`
+fn safe_join(dest: &Path, entry: &Path) -> Result<PathBuf, Error> {
+    let mut path = dest.to_path_buf();
+    for component in entry.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => (),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("archive entry {:?} escapes the target directory", entry),
+                ))
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Symlinks are only allowed when they point somewhere inside the archive
+fn check_link(entry: &Path, target: &Path) -> Result<(), Error> {
+    let base = entry.parent().unwrap_or_else(|| Path::new(""));
+    let mut depth: i32 = base
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .count() as i32;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => (),
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "link {:?} -> {:?} escapes the target directory",
+                        entry, target
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn extract_tar_gz(archive: &Path, dest: &Path) -> Result<(), Error> {
+    let mut tar = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_path_buf();
+        safe_join(dest, &entry_path)?;
+        if let Some(target) = entry.link_name()? {
+            if entry.header().entry_type().is_hard_link() {
+                // hard link targets are relative to the archive root
+                safe_join(dest, &target)?;
+            } else {
+                check_link(&entry_path, &target)?;
+            }
+        }
+        entry.unpack_in(dest)?;
+    }
+    Ok(())
+}
+
+fn extract_zip(archive: &Path, dest: &Path) -> Result<(), Error> {
+    let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+    for index in 0..zip.len() {
+        let mut file = zip.by_index(index)?;
+        let path = safe_join(dest, Path::new(file.name()))?;
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut file, &mut File::create(&path)?)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = file.unix_mode() {
+                fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o777))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn archive_name(url: &str) -> String {
+    url.split('?')
+        .next()
+        .unwrap_or(url)
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or("runtime.tar.gz")
+        .to_string()
+}
+
+/// Directory name for the unpacked archive, restricted to characters that are safe in a path
+fn runtime_name(archive_name: &str) -> String {
+    let stem = [".tar.gz", ".tgz", ".zip"]
+        .iter()
+        .find(|ext| archive_name.ends_with(*ext))
+        .map(|ext| &archive_name[..archive_name.len() - ext.len()])
+        .unwrap_or(archive_name);
+    let name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.+".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.trim_start_matches('.') {
+        "" => "runtime".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Expected SHA-256: the configured one, otherwise a `<url>.sha256` file next to the archive
+fn expected_checksum(url: &str, configured: Option<&str>) -> Result<String, Error> {
+    if let Some(checksum) = configured.filter(|checksum| !checksum.is_empty()) {
+        return Ok(checksum.trim().to_lowercase());
+    }
+    let mut data = String::new();
+    http_tools::load_file(&format!("{}.sha256", url))?.read_to_string(&mut data)?;
+    // `sha256sum` format: "<hash>  <file name>"
+    data.split_whitespace()
+        .next()
+        .map(|checksum| checksum.to_lowercase())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "empty checksum file"))
+}
+
+/// Downloads a portable JRE archive, verifies it and unpacks it into the runtimes directory.
+pub fn install_runtime(url: &str, checksum: Option<&str>) -> Result<JavaRuntime, Error> {
+    install_into(&runtimes_dir(), url, checksum)
+}
+
+/// Saves the archive to `archive` and checks its hash, the caller removes it on errors
+fn download_archive(url: &str, archive: &Path, expected: &str) -> Result<(), Error> {
+    let mut reader = http_tools::load_file(url)?;
+    let mut file = File::create(archive)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        file.write_all(&buffer[..read])?;
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    if actual != expected {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("checksum mismatch: expected {}, got {}", expected, actual),
+        ));
+    }
+    Ok(())
+}
+
+fn install_into(dir: &Path, url: &str, checksum: Option<&str>) -> Result<JavaRuntime, Error> {
+    let expected = expected_checksum(url, checksum)?;
+    fs::create_dir_all(dir)?;
+
+    let archive_name = archive_name(url);
+    let name = runtime_name(&archive_name);
+    // dot names are skipped by `remove_unused` while the install runs
+    let archive = dir.join(format!(".{}.download", name));
+    let staging = dir.join(format!(".{}.extract", name));
+    let target = dir.join(&name);
+
+    let extracted = download_archive(url, &archive, &expected).and_then(|_| {
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+        if archive_name.ends_with(".zip") {
+            extract_zip(&archive, &staging)
+        } else {
+            extract_tar_gz(&archive, &staging)
+        }
+    });
+    if let Err(e) = fs::remove_file(&archive) {
+        if e.kind() != ErrorKind::NotFound {
+            println!("cannot remove {}: {}", archive.display(), e);
+        }
+    }
+    if let Err(e) = extracted {
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        return Err(e);
+    }
+
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::rename(&staging, &target)?;
+
+    match find_java_binary(&target) {
+        Some(binary) => java::probe(&binary),
+        None => {
+            fs::remove_dir_all(&target)?;
+            Err(Error::new(
+                ErrorKind::NotFound,
+                "archive does not contain bin/java",
+            ))
+        }
+    }
+}
+
+/// Deletes managed runtimes that are not the global choice, not bound to a version and not
+/// the runtime a version falls back to. Without a system Java the fallback is a managed one.
+pub fn remove_unused(
+    selection: &RuntimeSelection,
+    runtimes: &[JavaRuntime],
+) -> Result<Vec<PathBuf>, Error> {
+    remove_unused_in(&runtimes_dir(), selection, runtimes)
+}
+
+fn remove_unused_in(
+    dir: &Path,
+    selection: &RuntimeSelection,
+    runtimes: &[JavaRuntime],
+) -> Result<Vec<PathBuf>, Error> {
+    // a tag without a binding resolves like every unbound version does
+    let fallback = selection.resolve("", runtimes).map(|runtime| &runtime.path);
+    let used: Vec<&PathBuf> = selection
+        .global
+        .iter()
+        .chain(selection.per_version.values())
+        .chain(fallback)
+        .collect();
+
+    let mut removed = Vec::new();
+    for entry in fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+        let root = entry.path();
+        // `.<name>.extract` belongs to an install that is still running
+        if !root.is_dir() || entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if used.iter().any(|path| path.starts_with(&root)) {
+            continue;
+        }
+        fs::remove_dir_all(&root)?;
+        removed.push(root);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml_core::test_support::{temp_dir, Response, TestServer};
+    use std::collections::HashMap;
+
+    /// A zip with `jre-17/bin/java` answering `-version` like OpenJDK 17
+    fn fixture_archive() -> Vec<u8> {
+        let script = "#!/bin/sh\n\
+            echo 'openjdk version \"17.0.2\" 2022-01-18' >&2\n\
+            echo 'OpenJDK Runtime Environment (build 17.0.2+8-86)' >&2\n\
+            echo 'OpenJDK 64-Bit Server VM (build 17.0.2+8-86, mixed mode, sharing)' >&2\n";
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default().unix_permissions(0o755);
+        zip.start_file(format!("jre-17/bin/{}", java::JAVA_BINARY), options)
+            .unwrap();
+        zip.write_all(script.as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    /// Serves the archive at `/jre.zip` and its hash at `/jre.zip.sha256`
+    fn archive_server(archive: Vec<u8>, cut: Option<usize>) -> TestServer {
+        let checksum = format!("{}  jre.zip\n", sha256(&archive));
+        TestServer::start(move |request| match request.path.as_str() {
+            "/jre.zip.sha256" => Response::ok(checksum.clone()),
+            "/jre.zip" => match cut {
+                Some(cut) => Response::ok(archive.clone()).cut(cut),
+                None => Response::ok(archive.clone()),
+            },
+            _ => Response::new(404, ""),
+        })
+    }
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with('.'))
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn installs_a_verified_archive() {
+        let server = archive_server(fixture_archive(), None);
+        let dir = temp_dir("runtime-install");
+        let runtime = install_into(&dir, &server.url("/jre.zip"), None).unwrap();
+        assert_eq!(runtime.major, 17);
+        assert_eq!(runtime.version, "17.0.2");
+        assert!(runtime.path.starts_with(dir.join("jre")));
+        assert!(leftovers(&dir).is_empty());
+    }
+
+    #[test]
+    fn removes_the_archive_of_a_failed_install() {
+        let archive = fixture_archive();
+        let server = archive_server(archive.clone(), None);
+        let dir = temp_dir("runtime-mismatch");
+        let error = install_into(&dir, &server.url("/jre.zip"), Some(&"0".repeat(64))).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(leftovers(&dir).is_empty());
+
+        let server = archive_server(archive.clone(), Some(archive.len() / 2));
+        let dir = temp_dir("runtime-cut");
+        assert!(install_into(&dir, &server.url("/jre.zip"), None).is_err());
+        assert!(leftovers(&dir).is_empty());
+        assert!(fs::read_dir(&dir).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn keeps_used_runtimes_and_running_installs() {
+        let dir = temp_dir("runtime-unused");
+        for name in ["used", "unused", ".jre.extract"].iter() {
+            fs::create_dir_all(dir.join(name).join("bin")).unwrap();
+        }
+        let selection = RuntimeSelection {
+            global: Some(dir.join("used").join("bin").join(java::JAVA_BINARY)),
+            per_version: HashMap::new(),
+        };
+        assert_eq!(
+            remove_unused_in(&dir, &selection, &[]).unwrap(),
+            vec![dir.join("unused")]
+        );
+        assert!(dir.join("used").is_dir());
+        assert!(dir.join(".jre.extract").is_dir());
+    }
+
+    #[test]
+    fn keeps_the_fallback_runtime() {
+        let dir = temp_dir("runtime-fallback");
+        fs::create_dir_all(dir.join("jre-17").join("bin")).unwrap();
+        let runtime = JavaRuntime {
+            path: dir.join("jre-17").join("bin").join(java::JAVA_BINARY),
+            version: "17.0.2".to_string(),
+            vendor: "OpenJDK".to_string(),
+            major: 17,
+        };
+        // nothing selected, every version runs on the only runtime there is
+        let selection = RuntimeSelection::default();
+        assert!(remove_unused_in(&dir, &selection, &[runtime])
+            .unwrap()
+            .is_empty());
+        assert!(dir.join("jre-17").is_dir());
+    }
+
+    /// A tar.gz with raw entry names, `tar::Builder` refuses the unsafe ones
+    fn tar_gz(entries: &[(&str, Option<&str>)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (name, link) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            let data: &[u8] = match link {
+                Some(target) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_link_name_literal(target).unwrap();
+                    b""
+                }
+                None => b"data",
+            };
+            header.set_size(data.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// A zip of plain files, or of one symlink entry when `link` is set
+    fn zip_archive(names: &[&str], link: Option<&str>) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        for name in names {
+            zip.start_file(*name, Default::default()).unwrap();
+            zip.write_all(link.unwrap_or("data").as_bytes()).unwrap();
+        }
+        let mut data = zip.finish().unwrap().into_inner();
+        if link.is_some() {
+            // the writer has no symlinks, set S_IFLNK in the central directory by hand
+            let central = data
+                .windows(4)
+                .position(|window| window == b"PK\x01\x02")
+                .unwrap();
+            let mode: u32 = 0o120_777 << 16;
+            data[central + 38..central + 42].copy_from_slice(&mode.to_le_bytes());
+        }
+        data
+    }
+
+    /// Extracts into `<dir>/dest`, everything outside of it is an escape
+    fn extract(name: &str, archive: &[u8], zip: bool) -> (PathBuf, Result<(), Error>) {
+        let dir = temp_dir(name);
+        let path = dir.join("archive");
+        fs::write(&path, archive).unwrap();
+        let dest = dir.join("dest");
+        fs::create_dir_all(&dest).unwrap();
+        let result = if zip {
+            extract_zip(&path, &dest)
+        } else {
+            extract_tar_gz(&path, &dest)
+        };
+        (dir, result)
+    }
+
+    fn escaped(dir: &Path) -> bool {
+        ["evil", "absolute", "outside"]
+            .iter()
+            .any(|name| dir.join(name).exists())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extracts_tar_gz_archives() {
+        let archive = tar_gz(&[
+            ("jre/lib/libjava.so", None),
+            ("jre/bin/java", None),
+            ("jre/bin/libjava.so", Some("../lib/libjava.so")),
+        ]);
+        let (dir, result) = extract("extract-tar", &archive, false);
+        result.unwrap();
+        let dest = dir.join("dest");
+        assert_eq!(fs::read(dest.join("jre/bin/java")).unwrap(), b"data");
+        assert_eq!(
+            fs::read_link(dest.join("jre/bin/libjava.so")).unwrap(),
+            Path::new("../lib/libjava.so")
+        );
+    }
+
+    #[test]
+    fn rejects_tar_gz_entries_outside_the_target() {
+        let dir = temp_dir("escape-tar");
+        let absolute = dir.join("absolute").to_string_lossy().to_string();
+        let archives = [
+            tar_gz(&[("jre/../../evil", None)]),
+            tar_gz(&[(absolute.as_str(), None)]),
+            tar_gz(&[("jre/link", Some("../../outside"))]),
+            tar_gz(&[("jre/link", Some("/tmp"))]),
+        ];
+        for (index, archive) in archives.iter().enumerate() {
+            let (dir, result) = extract(&format!("escape-tar-{}", index), archive, false);
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+            assert!(!escaped(&dir));
+        }
+        assert!(!Path::new(&absolute).exists());
+    }
+
+    #[test]
+    fn rejects_zip_entries_outside_the_target() {
+        let dir = temp_dir("escape-zip");
+        let absolute = dir.join("absolute").to_string_lossy().to_string();
+        for (index, name) in ["jre/../../evil", absolute.as_str()].iter().enumerate() {
+            let archive = zip_archive(&[name], None);
+            let (dir, result) = extract(&format!("escape-zip-{}", index), &archive, true);
+            assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+            assert!(!escaped(&dir));
+        }
+        assert!(!Path::new(&absolute).exists());
+
+        // zip symlinks are unpacked as plain files holding the target
+        let archive = zip_archive(&["jre/link"], Some("../../outside"));
+        let (dir, result) = extract("escape-zip-link", &archive, true);
+        result.unwrap();
+        let link = dir.join("dest").join("jre").join("link");
+        assert!(!fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert!(!escaped(&dir));
+    }
+}
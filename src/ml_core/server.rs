@@ -0,0 +1,75 @@
+use super::download;
+use super::integrity::{self, Checksum};
+use super::java::JavaRuntime;
+use super::paths;
+use super::process::GameProcess;
+use crate::models::profile::LaunchProfile;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The server keeps its `config/` (maps, saves, settings) here, shared by every version
+pub fn server_dir() -> PathBuf {
+    paths::data_dir().join("server")
+}
+
+pub fn server_jar(tag_name: &str) -> PathBuf {
+    server_dir().join(format!(
+        "server-{}.jar",
+        download::sanitize_file_name(tag_name)
+    ))
+}
+
+/// Downloads and verifies the server jar, a partial or corrupted file never takes the place of a finished one
+pub fn download(
+    url: &str,
+    tag_name: &str,
+    expected_size: Option<u64>,
+    checksum: Option<&Checksum>,
+) -> Result<PathBuf, Error> {
+    let jar = server_jar(tag_name);
+    let sha256 = checksum.map_or(Ok(None), Checksum::resolve)?;
+    download::download_file(
+        url,
+        &jar,
+        expected_size,
+        &|part| integrity::verify(part, sha256.as_deref()),
+        &mut |_| true,
+    )?;
+    Ok(jar)
+}
+
+/// Starts the server without a window, commands go to the returned handle with `send_line`
+pub fn start(java: Option<&JavaRuntime>, tag_name: &str) -> Result<GameProcess, Error> {
+    let jar = server_jar(tag_name);
+    if !jar.is_file() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("server {} is not downloaded", tag_name),
+        ));
+    }
+    let java_path = java
+        .map(|runtime| runtime.path.clone())
+        .unwrap_or_else(|| PathBuf::from("java"));
+
+    let mut command = Command::new(java_path);
+    command
+        .arg("-Djava.awt.headless=true")
+        .arg("-jar")
+        .arg(&jar)
+        .current_dir(server_dir());
+
+    let profile = LaunchProfile {
+        name: "server".to_string(),
+        tag_name: Some(tag_name.to_string()),
+        ..LaunchProfile::default()
+    };
+    GameProcess::spawn(
+        command,
+        &format!("server-{}", tag_name),
+        &jar,
+        &profile,
+        java,
+        server_dir().join("config"),
+    )
+}
@@ -0,0 +1,181 @@
+use super::providers;
+use crate::models::profile::Channel;
+use crate::models::source::{ReleaseSource, SourceKind, SourcesFile};
+use crate::models::version::AssetRules;
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+/// Optional, without it the launcher lists the official repositories
+pub const SOURCES_FILE: &str = "Sources.toml";
+
+const OFFICIAL: &str = "Mindustry";
+const BLEEDING_EDGE: &str = "Bleeding edge";
+
+/// The official repositories, used when `Sources.toml` does not exist
+pub fn default_sources() -> Vec<ReleaseSource> {
+    vec![
+        ReleaseSource {
+            name: OFFICIAL.to_string(),
+            kind: SourceKind::Github,
+            repo: Some("Anuken/Mindustry".to_string()),
+            api_url: None,
+            path: None,
+            channel: Channel::Stable,
+            color: [110, 110, 110],
+            assets: AssetRules::default(),
+        },
+        ReleaseSource {
+            name: BLEEDING_EDGE.to_string(),
+            kind: SourceKind::Github,
+            repo: Some("Anuken/MindustryBuilds".to_string()),
+            api_url: None,
+            path: None,
+            channel: Channel::BleedingEdge,
+            color: [180, 110, 110],
+            assets: AssetRules::default(),
+        },
+    ]
+}
+
+/// Whether `name` is one of the default sources, their tags never collide
+pub fn is_default(name: &str) -> bool {
+    name == OFFICIAL || name == BLEEDING_EDGE
+}
+
+pub fn load() -> Result<Vec<ReleaseSource>, Error> {
+    match fs::read_to_string(SOURCES_FILE) {
+        Ok(data) => parse(&data),
+        Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(default_sources()),
+        Err(e) => Err(e),
+    }
+}
+
+fn parse(data: &str) -> Result<Vec<ReleaseSource>, Error> {
+    let file: SourcesFile =
+        toml::from_str(data).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    // catch a missing repo or path now instead of on every refresh
+    for source in file.source.iter() {
+        providers::for_source(source)?;
+    }
+    Ok(file.source)
+}
+
+fn is_owner_repo(repo: &str) -> bool {
+    let parts: Vec<&str> = repo.split('/').collect();
+    parts.len() == 2 && parts.iter().all(|part| !part.trim().is_empty())
+}
+
+/// The `/releases` endpoint of a source: `api_url` as is, on GitHub it can be built from `repo`
+pub fn releases_url(source: &ReleaseSource) -> Result<String, Error> {
+    match (&source.api_url, &source.repo) {
+        (Some(api_url), _) if !api_url.is_empty() => Ok(api_url.clone()),
+        (_, Some(repo)) if source.kind == SourceKind::Github && is_owner_repo(repo) => {
+            Ok(format!("https://api.github.com/repos/{}/releases", repo))
+        }
+        _ if source.kind == SourceKind::Github => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "source {} needs repo = \"owner/repo\" or api_url",
+                source.name
+            ),
+        )),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("source {} needs api_url", source.name),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn github(repo: Option<&str>, api_url: Option<&str>) -> ReleaseSource {
+        ReleaseSource {
+            name: "fork".to_string(),
+            repo: repo.map(str::to_string),
+            api_url: api_url.map(str::to_string),
+            ..ReleaseSource::default()
+        }
+    }
+
+    #[test]
+    fn builds_releases_urls() {
+        assert_eq!(
+            releases_url(&github(Some("Anuken/Mindustry"), None)).unwrap(),
+            "https://api.github.com/repos/Anuken/Mindustry/releases"
+        );
+        // api_url wins over repo, an empty one does not count
+        let mirror = "https://mirror.example/api/v1/repos/a/b/releases";
+        assert_eq!(
+            releases_url(&github(Some("Anuken/Mindustry"), Some(mirror))).unwrap(),
+            mirror
+        );
+        assert_eq!(
+            releases_url(&github(Some("Anuken/Mindustry"), Some(""))).unwrap(),
+            "https://api.github.com/repos/Anuken/Mindustry/releases"
+        );
+        for repo in ["Anuken", "Anuken/Mindustry/extra", "Anuken/", "/", ""].iter() {
+            let error = releases_url(&github(Some(repo), None)).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", repo);
+        }
+        assert!(releases_url(&github(None, None)).is_err());
+
+        // only GitHub knows where `owner/repo` lives
+        let gitea = ReleaseSource {
+            kind: SourceKind::Gitea,
+            ..github(Some("a/b"), None)
+        };
+        assert!(releases_url(&gitea).is_err());
+        let gitea = ReleaseSource {
+            api_url: Some(mirror.to_string()),
+            ..gitea
+        };
+        assert_eq!(releases_url(&gitea).unwrap(), mirror);
+    }
+
+    #[test]
+    fn parses_sources_files() {
+        let sources = parse(
+            r#"
+            [[source]]
+            name = "Mindustry"
+            repo = "Anuken/Mindustry"
+            channel = "stable"
+
+            [[source]]
+            name = "mirror"
+            kind = "gitea"
+            api_url = "https://mirror.example/api/v1/repos/a/b/releases"
+            channel = "custom"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].kind, SourceKind::Github);
+        assert_eq!(sources[0].color, [110, 110, 110]);
+        assert_eq!(sources[1].channel, Channel::Custom);
+
+        let missing_channel = parse(
+            r#"
+            [[source]]
+            name = "fork"
+            repo = "a/b"
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(missing_channel.kind(), ErrorKind::InvalidData);
+        assert!(missing_channel.to_string().contains("channel"));
+
+        let bad_repo = parse(
+            r#"
+            [[source]]
+            name = "fork"
+            repo = "just-a-name"
+            channel = "custom"
+            "#,
+        )
+        .unwrap_err();
+        assert!(bad_repo.to_string().contains("fork"));
+    }
+}
@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+
+/// A Mindustry version parsed from a tag or release name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameVersion {
+    /// A numbered release: `v104.6` is major 104, minor 6. `v146` has minor 0.
+    Release { major: u32, minor: u32 },
+    /// A bleeding-edge build like `25872` or `Build 25872`. Builds are numbered on their own,
+    /// so a build is neither older nor newer than a release by itself.
+    Build(u32),
+}
+
+impl GameVersion {
+    /// Parses `v126.2`, `v146`, `126.2-rc1`, `25872` and `Build 25872`
+    pub fn parse(text: &str) -> Option<GameVersion> {
+        let text = text.trim();
+        let build = text
+            .strip_prefix("Build ")
+            .or_else(|| text.strip_prefix("build "))
+            .unwrap_or(text);
+        if !build.is_empty() && build.chars().all(|c| c.is_ascii_digit()) {
+            return build.parse().ok().map(GameVersion::Build);
+        }
+        if !text.starts_with(['v', 'V']) && !text.contains('.') {
+            return None;
+        }
+
+        // the numbers end at the first suffix like `-rc1`
+        let numbers = text.trim_start_matches(['v', 'V']);
+        let end = numbers
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(numbers.len());
+        let mut parts = numbers[..end].split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = match parts.next() {
+            Some(minor) => minor.parse().ok()?,
+            None => 0,
+        };
+        Some(GameVersion::Release { major, minor })
+    }
+}
+
+impl PartialOrd for GameVersion {
+    /// `None` between a release and a build, they need the publish date to be ordered
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (
+                GameVersion::Release { major, minor },
+                GameVersion::Release {
+                    major: other_major,
+                    minor: other_minor,
+                },
+            ) => Some((major, minor).cmp(&(other_major, other_minor))),
+            (GameVersion::Build(build), GameVersion::Build(other_build)) => {
+                Some(build.cmp(other_build))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(major: u32, minor: u32) -> Option<GameVersion> {
+        Some(GameVersion::Release { major, minor })
+    }
+
+    #[test]
+    fn parses_tags_and_names() {
+        assert_eq!(GameVersion::parse("v104.6"), release(104, 6));
+        assert_eq!(GameVersion::parse("v126.2"), release(126, 2));
+        assert_eq!(GameVersion::parse("V146"), release(146, 0));
+        assert_eq!(GameVersion::parse(" 126.2-rc1 "), release(126, 2));
+        assert_eq!(GameVersion::parse("v104.10"), release(104, 10));
+        assert_eq!(GameVersion::parse("25872"), Some(GameVersion::Build(25872)));
+        assert_eq!(
+            GameVersion::parse("Build 25872"),
+            Some(GameVersion::Build(25872))
+        );
+        assert_eq!(GameVersion::parse("build 7"), Some(GameVersion::Build(7)));
+        for text in ["", "v", "vx.1", "custom", "Build", "Build x"].iter() {
+            assert_eq!(GameVersion::parse(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn orders_releases_and_builds_apart() {
+        assert!(release(104, 6) < release(104, 10));
+        assert!(release(104, 10) < release(126, 2));
+        assert!(release(126, 2) < release(146, 0));
+        assert!(GameVersion::Build(20000) < GameVersion::Build(25872));
+        let (build, numbered) = (
+            GameVersion::Build(25872),
+            GameVersion::Release {
+                major: 146,
+                minor: 0,
+            },
+        );
+        assert_eq!(build.partial_cmp(&numbered), None);
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// A release as the Gitea/Forgejo API `/repos/{owner}/{repo}/releases` returns it
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GiteaRelease {
+    pub id: i64,
+    pub tag_name: Option<String>,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub html_url: Option<String>,
+    #[serde(default)]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<GiteaAsset>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GiteaAsset {
+    pub id: i64,
+    pub name: String,
+    pub size: i64,
+    pub browser_download_url: String,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A jar the launcher installed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstallRecord {
+    pub tag_name: String,
+    /// Name of the release source
+    pub source: String,
+    /// Repository, API URL or directory the source reads from
+    #[serde(default)]
+    pub repo: Option<String>,
+    pub asset_name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    pub installed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_played: Option<DateTime<Utc>>,
+    /// Total time the game ran, in seconds
+    #[serde(default)]
+    pub play_time: u64,
+}
+
+/// `installs.json` in the launcher data directory
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct InstallDatabase {
+    #[serde(default)]
+    pub installs: Vec<InstallRecord>,
+}
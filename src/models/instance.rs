@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A separate Mindustry setup: its own jars and its own saves, mods, maps and settings
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Instance {
+    pub name: String,
+    /// Installation directory, the game data lives in `<path>/data`
+    pub path: String,
+}
+
+impl Instance {
+    /// Root handed to the game as its app data directory
+    pub fn data_root(&self) -> PathBuf {
+        Path::new(&self.path).join("data")
+    }
+
+    /// The `Mindustry` folder the game creates inside the data root
+    pub fn game_data_dir(&self) -> PathBuf {
+        if cfg!(target_os = "macos") {
+            self.data_root()
+                .join("Library")
+                .join("Application Support")
+                .join("Mindustry")
+        } else {
+            self.data_root().join("Mindustry")
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct InstancesFile {
+    pub selected: String,
+    #[serde(default)]
+    pub instance: Vec<Instance>,
+}
@@ -0,0 +1,34 @@
+use super::version::ReleaseAsset;
+use serde::{Deserialize, Serialize};
+
+/// Static release list for sources without an API:
+///
+/// ```json
+/// { "releases": [ { "tag_name": "v126", "name": "Build 126",
+///     "assets": [ { "name": "Mindustry.jar", "size": 123, "download_url": "https://...",
+///         "sha256": "<hex, optional>" } ] } ] }
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub releases: Vec<ManifestRelease>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestRelease {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Release notes in Markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub html_url: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
+    /// RFC 3339, e.g. `2023-05-04T12:00:00Z`
+    #[serde(default)]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
@@ -1 +1,10 @@
-pub mod release;
\ No newline at end of file
+pub mod game_version;
+pub mod gitea;
+pub mod install;
+pub mod instance;
+pub mod manifest;
+pub mod profile;
+pub mod release;
+pub mod release_cache;
+pub mod source;
+pub mod version;
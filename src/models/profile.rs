@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Channel {
+    #[default]
+    Stable,
+    BleedingEdge,
+    /// Forks and community clients
+    Custom,
+}
+
+/// One `[[profile]]` entry of `Profiles.toml`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaunchProfile {
+    pub name: String,
+    /// Value for `-Xmx`, e.g. `2G` or `512M`
+    #[serde(default)]
+    pub max_heap: Option<String>,
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub game_args: Vec<String>,
+    /// Bind the profile to one release
+    #[serde(default)]
+    pub tag_name: Option<String>,
+    /// Bind the profile to every release of a channel
+    #[serde(default)]
+    pub channel: Option<Channel>,
+    // tables go last, TOML can not have plain values after them
+    /// Passed as `-Dkey=value`
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ProfilesFile {
+    #[serde(default)]
+    pub profile: Vec<LaunchProfile>,
+}
@@ -92,6 +92,8 @@ pub struct Asset {
     pub updated_at: Option<String>,
     #[serde(rename = "browser_download_url")]
     pub browser_download_url: Option<String>,
+    /// `sha256:<hex>`, only on assets uploaded since GitHub started hashing them
+    pub digest: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -0,0 +1,23 @@
+use super::release::Root;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One `/releases` page as the API returned it, with the validators for conditional requests
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedPage {
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    /// `rel="next"` link of the page
+    #[serde(default)]
+    pub next: Option<String>,
+    pub releases: Vec<Root>,
+}
+
+/// Pages keyed by their URL
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseCache {
+    #[serde(default)]
+    pub pages: BTreeMap<String, CachedPage>,
+}
@@ -0,0 +1,53 @@
+use super::profile::Channel;
+use super::version::AssetRules;
+use serde::{Deserialize, Serialize};
+
+/// Backend a source is read with
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourceKind {
+    #[default]
+    Github,
+    /// Gitea and Forgejo, both serve the same releases API
+    Gitea,
+    /// A static JSON file, see `models::manifest`
+    Manifest,
+    /// A local directory of jars
+    Directory,
+}
+
+/// One `[[source]]` entry of `Sources.toml`: a repository the launcher lists releases from
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseSource {
+    /// Shown next to its releases and stored with installed versions
+    pub name: String,
+    #[serde(default)]
+    pub kind: SourceKind,
+    /// `owner/repo` on GitHub
+    #[serde(default)]
+    pub repo: Option<String>,
+    /// Full URL of the releases API, for mirrors and self-hosted forks. Used instead of `repo`.
+    /// For `manifest` sources this is the URL of the manifest.
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// Jar directory of `directory` sources
+    #[serde(default)]
+    pub path: Option<String>,
+    pub channel: Channel,
+    /// List row color as `[r, g, b]`
+    #[serde(default = "default_color")]
+    pub color: [u8; 3],
+    // a table, it has to stay the last field like the maps of `LaunchProfile`
+    #[serde(default)]
+    pub assets: AssetRules,
+}
+
+fn default_color() -> [u8; 3] {
+    [110, 110, 110]
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct SourcesFile {
+    #[serde(default)]
+    pub source: Vec<ReleaseSource>,
+}
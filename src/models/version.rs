@@ -0,0 +1,69 @@
+use super::game_version::GameVersion;
+use super::profile::Channel;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A downloadable file of a release
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub size: u64,
+    pub download_url: String,
+    /// Lowercase hex SHA-256 when the source publishes one
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// Name patterns for picking assets, `*` matches any run of characters and case is ignored.
+/// Patterns are tried in order, so exact names go before fallbacks like `*.jar`.
+/// The desktop jar never matches a server pattern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetRules {
+    #[serde(default)]
+    pub desktop: Vec<String>,
+    #[serde(default)]
+    pub server: Vec<String>,
+}
+
+impl Default for AssetRules {
+    fn default() -> Self {
+        AssetRules {
+            // bleeding-edge builds are `Mindustry-BE-Desktop-N.jar` and `Mindustry-BE-Server-N.jar`
+            desktop: vec![
+                "Mindustry.jar".to_string(),
+                "*desktop*.jar".to_string(),
+                "*.jar".to_string(),
+            ],
+            server: vec!["server-release.jar".to_string(), "*server*.jar".to_string()],
+        }
+    }
+}
+
+/// A version as the launcher shows it, whatever source it came from
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Release {
+    pub name: String,
+    pub tag_name: String,
+    /// Parsed from the tag, or from the name when the tag is not a version
+    pub version: Option<GameVersion>,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Every asset of the release, including the ones no rule picked
+    pub assets: Vec<ReleaseAsset>,
+    /// Desktop jar, `None` when the release is not installable
+    pub desktop: Option<ReleaseAsset>,
+    /// Dedicated server jar, not every release has one
+    pub server: Option<ReleaseAsset>,
+    /// Release notes in Markdown
+    pub body: Option<String>,
+    /// Release page on the web
+    pub html_url: Option<String>,
+    /// Marked as a pre-release by its source
+    pub prerelease: bool,
+    pub installed: bool,
+    /// The installed jar, set together with `installed`
+    pub jar: Option<PathBuf>,
+    pub channel: Channel,
+    /// Name of the source the release came from
+    pub source: String,
+}